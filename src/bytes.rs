@@ -0,0 +1,393 @@
+use crate::sequences::{self, Escape, ParseOptions};
+use std::{borrow::Cow, fmt::Write, str};
+
+/// How to handle bytes which aren't valid UTF-8.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InvalidBytes {
+    /// Replace invalid bytes with U+FFFD REPLACEMENT CHARACTER, just like [`String::from_utf8_lossy`].
+    #[default]
+    Replace,
+    /// Show every invalid byte as a hex escape (`\xNN`).
+    Hex,
+}
+impl InvalidBytes {
+    /// Decode bytes, applying the invalid byte handling.
+    /// Only allocates if the bytes contain invalid UTF-8.
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        if let Ok(s) = str::from_utf8(bytes) {
+            return Cow::Borrowed(s);
+        }
+
+        let mut s = String::with_capacity(bytes.len());
+        for (valid, invalid) in utf8_chunks(bytes) {
+            s.push_str(valid);
+            self.push_invalid(&mut s, invalid);
+        }
+        Cow::Owned(s)
+    }
+
+    /// Append the replacement for the invalid bytes to the string.
+    pub(crate) fn push_invalid(self, s: &mut String, invalid: &[u8]) {
+        if invalid.is_empty() {
+            return;
+        }
+        match self {
+            Self::Replace => s.push(char::REPLACEMENT_CHARACTER),
+            Self::Hex => {
+                for b in invalid {
+                    let _ = write!(s, "\\x{:02x}", b);
+                }
+            }
+        }
+    }
+}
+
+/// Split the bytes into valid UTF-8 and the invalid bytes following it.
+///
+/// Works like `<[u8]>::utf8_chunks`: each item is a (possibly empty) valid string slice
+/// followed by a (possibly empty) sequence of bytes making up a single invalid character.
+pub(crate) fn utf8_chunks(mut bytes: &[u8]) -> impl Iterator<Item = (&str, &[u8])> {
+    std::iter::from_fn(move || {
+        let current = bytes;
+        if current.is_empty() {
+            return None;
+        }
+        let (valid_len, invalid_len) = match str::from_utf8(current) {
+            Ok(_) => (current.len(), 0),
+            Err(err) => (
+                err.valid_up_to(),
+                err.error_len().unwrap_or(current.len() - err.valid_up_to()),
+            ),
+        };
+        let (valid, rest) = current.split_at(valid_len);
+        let (invalid, rest) = rest.split_at(invalid_len);
+        bytes = rest;
+        // SAFETY: the bytes up to `valid_up_to` are valid UTF-8
+        Some((unsafe { str::from_utf8_unchecked(valid) }, invalid))
+    })
+}
+
+/// Check whether the invalid bytes returned by [`utf8_chunks`] are the start of a character
+/// which is cut off, rather than invalid.
+pub(crate) fn is_incomplete_char(invalid: &[u8]) -> bool {
+    !invalid.is_empty() && matches!(str::from_utf8(invalid), Err(err) if err.error_len().is_none())
+}
+
+/// Check whether the invalid bytes are a single raw byte which is decoded as a C1 control.
+fn is_raw_c1_control(invalid: &[u8], options: ParseOptions) -> bool {
+    options.c1_controls && matches!(invalid, [0x80..=0x9f])
+}
+
+/// Find the first byte which starts an escape sequence.
+fn find_introducer(bytes: &[u8], options: ParseOptions) -> Option<usize> {
+    let mut offset = 0;
+    for (valid, invalid) in utf8_chunks(bytes) {
+        if let Some(index) = valid.find(|c| options.is_introducer(c)) {
+            return Some(offset + index);
+        }
+        offset += valid.len();
+        if is_raw_c1_control(invalid, options) {
+            return Some(offset);
+        }
+        offset += invalid.len();
+    }
+    None
+}
+
+/// Lossy decoded bytes which keeps track of where the replacement characters are.
+#[derive(Clone, Debug)]
+struct LossyText<'a> {
+    text: Cow<'a, str>,
+    /// Offset of each replacement character in `text`, the number of bytes it replaces, and its length.
+    replacements: Vec<(usize, usize, usize)>,
+}
+impl<'a> LossyText<'a> {
+    /// Decode the bytes.
+    /// If C1 controls are enabled, a single invalid byte in the C1 range is decoded as that control.
+    fn new(bytes: &'a [u8], options: ParseOptions) -> Self {
+        if let Ok(s) = str::from_utf8(bytes) {
+            return Self {
+                text: Cow::Borrowed(s),
+                replacements: Vec::new(),
+            };
+        }
+
+        let mut text = String::with_capacity(bytes.len());
+        let mut replacements = Vec::new();
+        for (valid, invalid) in utf8_chunks(bytes) {
+            text.push_str(valid);
+            if invalid.is_empty() {
+                continue;
+            }
+            let replacement = if is_raw_c1_control(invalid, options) {
+                char::from(invalid[0])
+            } else {
+                char::REPLACEMENT_CHARACTER
+            };
+            replacements.push((text.len(), invalid.len(), replacement.len_utf8()));
+            text.push(replacement);
+        }
+        Self {
+            text: Cow::Owned(text),
+            replacements,
+        }
+    }
+
+    /// Map an offset in the decoded text to the offset in the original bytes.
+    fn byte_offset(&self, offset: usize) -> usize {
+        self.replacements
+            .iter()
+            .take_while(|(pos, _, _)| *pos < offset)
+            .fold(offset, |offset, (_, len, replacement_len)| {
+                offset + len - replacement_len
+            })
+    }
+}
+
+/// Parts of a byte slice containing ANSI escape sequences.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ByteMarker<'a> {
+    /// Decoded text without any escape sequences.
+    /// This is only allocated if the text contains invalid UTF-8.
+    Text(Cow<'a, str>),
+    /// Parsed escape sequence.
+    Sequence(Escape),
+}
+
+/// Iterator yielding markers in a byte slice.
+///
+/// Each item is a [`ByteMarker`].
+///
+/// Returned by [`get_markers_bytes`] and [`get_markers_bytes_with_options`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct ByteMarkerIter<'a> {
+    bytes: &'a [u8],
+    invalid_bytes: InvalidBytes,
+    options: ParseOptions,
+    /// Position in the bytes.
+    pos: usize,
+}
+impl<'a> ByteMarkerIter<'a> {
+    fn new(bytes: &'a [u8], invalid_bytes: InvalidBytes, options: ParseOptions) -> Self {
+        Self {
+            bytes,
+            invalid_bytes,
+            options,
+            pos: 0,
+        }
+    }
+
+    /// Get the bytes which might belong to the escape sequence at the start of the slice.
+    ///
+    /// Escape sequences can't contain an introducer other than an escaped ESC (`ESC ESC`),
+    /// so this is everything up to the next introducer plus the string terminator if it follows.
+    fn sequence_bytes(&self, bytes: &'a [u8]) -> &'a [u8] {
+        let mut end = if bytes.starts_with(&[0xc2]) { 2 } else { 1 };
+        loop {
+            end = find_introducer(&bytes[end..], self.options)
+                .map_or(bytes.len(), |index| end + index);
+            if !bytes[end..].starts_with(b"\x1b\x1b") {
+                break;
+            }
+            end += 2;
+        }
+        let rest = &bytes[end..];
+        if rest.starts_with(b"\x1b\\") {
+            end += 2;
+        } else if self.options.c1_controls {
+            if rest.starts_with(&[0xc2, 0x9c]) {
+                end += 2;
+            } else if rest.starts_with(&[0x9c]) {
+                end += 1;
+            }
+        }
+        &bytes[..end]
+    }
+}
+impl<'a> Iterator for ByteMarkerIter<'a> {
+    type Item = ByteMarker<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self
+            .bytes
+            .get(self.pos..)
+            .filter(|bytes| !bytes.is_empty())?;
+        let text_len = find_introducer(bytes, self.options).unwrap_or(bytes.len());
+        if text_len > 0 {
+            self.pos += text_len;
+            return Some(ByteMarker::Text(
+                self.invalid_bytes.decode(&bytes[..text_len]),
+            ));
+        }
+
+        // only the escape sequence itself is decoded, invalid bytes in it are always replaced
+        let lossy = LossyText::new(self.sequence_bytes(bytes), self.options);
+        let (_, esc, post) = sequences::read_next_sequence_with_options(&lossy.text, self.options);
+        self.pos += lossy.byte_offset(lossy.text.len() - post.len());
+        esc.map(ByteMarker::Sequence)
+    }
+}
+
+/// Iterate over all [`ByteMarker`]s in the given bytes.
+///
+/// Escape sequences are recognised even if the text around them isn't valid UTF-8.
+/// Invalid bytes in text are handled according to `invalid_bytes`,
+/// invalid bytes inside of escape sequences are always replaced with U+FFFD.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let markers = yew_ansi::get_markers_bytes(b"caf\xe9 \x1b[1mok", InvalidBytes::Hex).collect::<Vec<_>>();
+/// assert_eq!(
+///     markers,
+///     vec![
+///         ByteMarker::Text("caf\\xe9 ".into()),
+///         ByteMarker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+///         ByteMarker::Text("ok".into()),
+///     ]
+/// );
+/// ```
+pub fn get_markers_bytes(bytes: &[u8], invalid_bytes: InvalidBytes) -> ByteMarkerIter<'_> {
+    ByteMarkerIter::new(bytes, invalid_bytes, ParseOptions::default())
+}
+
+/// Same as [`get_markers_bytes`] but with the given [`ParseOptions`].
+///
+/// With [`ParseOptions::c1_controls`] enabled, the raw bytes 0x80 to 0x9F are also recognised as C1 controls
+/// as long as they aren't part of a UTF-8 character.
+pub fn get_markers_bytes_with_options(
+    bytes: &[u8],
+    invalid_bytes: InvalidBytes,
+    options: ParseOptions,
+) -> ByteMarkerIter<'_> {
+    ByteMarkerIter::new(bytes, invalid_bytes, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graphic_rendition::Sgr, osc::Osc, sequences::Csi};
+
+    #[test]
+    fn decode() {
+        assert!(matches!(
+            InvalidBytes::Replace.decode(b"abc"),
+            Cow::Borrowed("abc")
+        ));
+        assert_eq!(InvalidBytes::Replace.decode(b"a\xffb"), "a\u{fffd}b");
+        assert_eq!(InvalidBytes::Hex.decode(b"a\xff\xfeb"), "a\\xff\\xfeb");
+    }
+
+    #[test]
+    fn marking() {
+        let markers = get_markers_bytes(
+            b"\xff\x1b]0;caf\xe9\x07\x1b[1mvalid\x1b[0m\xe2\x82",
+            InvalidBytes::Replace,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                ByteMarker::Text("\u{fffd}".into()),
+                ByteMarker::Sequence(Escape::Osc(Osc::SetIconNameAndTitle(
+                    "caf\u{fffd}".to_owned()
+                ))),
+                ByteMarker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+                ByteMarker::Text("valid".into()),
+                ByteMarker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Reset]))),
+                ByteMarker::Text("\u{fffd}".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn marking_c1_controls() {
+        let options = ParseOptions { c1_controls: true };
+        let markers =
+            get_markers_bytes_with_options(b"\x9b1m\xe9\xc2\x9b4m", InvalidBytes::Hex, options)
+                .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                ByteMarker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+                ByteMarker::Text("\\xe9".into()),
+                ByteMarker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Underline]))),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_terminators() {
+        let options = ParseOptions { c1_controls: true };
+        let markers = get_markers_bytes_with_options(
+            b"\x1b]2;\xff\x1b\\a\xc2\x9d2;b\x9c\xc2\x9b1mc",
+            InvalidBytes::Hex,
+            options,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                ByteMarker::Sequence(Escape::Osc(Osc::SetTitle("\u{fffd}".to_owned()))),
+                ByteMarker::Text("a".into()),
+                ByteMarker::Sequence(Escape::Osc(Osc::SetTitle("b".to_owned()))),
+                ByteMarker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+                ByteMarker::Text("c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_esc() {
+        let markers = get_markers_bytes(b"a\x1bPtmux;\x1b\x1b[1m\xff\x1b\\b", InvalidBytes::Hex)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                ByteMarker::Text("a".into()),
+                ByteMarker::Sequence(Escape::Dcs(crate::Dcs {
+                    params: Vec::new(),
+                    intermediates: String::new(),
+                    final_byte: 't',
+                    data: "mux;\u{001b}\u{001b}[1m\u{fffd}".to_owned(),
+                })),
+                ByteMarker::Text("b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_text_is_borrowed() {
+        let markers = get_markers_bytes(b"\xff\x1b[1mvalid", InvalidBytes::Hex).collect::<Vec<_>>();
+        assert!(matches!(
+            markers[2],
+            ByteMarker::Text(Cow::Borrowed("valid"))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_wire_format() {
+        let markers = get_markers_bytes(b"\x1b[1ma\xff", InvalidBytes::Hex).collect::<Vec<_>>();
+        let json = serde_json::to_string(&markers).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"Sequence":{"Csi":{"Sgr":["Bold"]}}},{"Text":"a\\xff"}]"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Vec<ByteMarker>>(&json).unwrap(),
+            markers
+        );
+        // serialized like a marker
+        assert_eq!(
+            serde_json::to_string(&ByteMarker::Text("a".into())).unwrap(),
+            serde_json::to_string(&crate::Marker::Text("a")).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_string(&InvalidBytes::Hex).unwrap(),
+            r#""Hex""#
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{
+    cursor::CharCursor,
+    diagnostics::ParseErrorKind,
+    sequences::{Csi, ParseOptions},
+};
+use std::fmt::{self, Display, Formatter};
+
+/// Read everything up to the string terminator, which is consumed as well.
+///
+/// The terminator is ST (`ESC \`, or U+009C if C1 controls are enabled) and,
+/// if `bel` is true, also BEL.
+/// If `escaped_esc` is true, `ESC ESC` is part of the string as an escaped ESC.
+/// Returns `None` if the string isn't terminated.
+/// The string is aborted by any other escape sequence, which isn't consumed.
+pub(crate) fn read_payload<'a>(
+    cursor: &mut CharCursor<'a>,
+    options: ParseOptions,
+    bel: bool,
+    escaped_esc: bool,
+) -> Option<&'a str> {
+    const BEL: char = '\u{0007}';
+    const ESC: char = '\u{001b}';
+    const ST: &str = "\u{001b}\\";
+    const ST_C1: char = '\u{009c}';
+    let start = cursor.position();
+    loop {
+        cursor.read_while(|c| !(options.is_introducer(c) || (bel && c == BEL)));
+        if !(escaped_esc && cursor.remainder().starts_with("\u{001b}\u{001b}")) {
+            break;
+        }
+        cursor.read_char(ESC);
+        cursor.read_char(ESC);
+    }
+    let end = cursor.position();
+
+    let terminated = (bel && cursor.read_char(BEL).is_some())
+        || (options.c1_controls && cursor.read_char(ST_C1).is_some());
+    if !terminated {
+        let rest = cursor.remainder();
+        // an ESC that doesn't start ST aborts the sequence, so it mustn't be consumed.
+        if !(rest.starts_with(ST) || ST.starts_with(rest)) {
+            return None;
+        }
+        cursor.read_char(ESC)?;
+        cursor.read_char('\\')?;
+    }
+
+    cursor.get(start..end)
+}
+
+/// Device Control String.
+///
+/// Sent to the terminal itself, for example by tmux to pass sequences through to the outer terminal.
+/// The header is structured like a control sequence and followed by arbitrary data.
+/// An ESC in the data is escaped by doubling it, which is how tmux passes escape sequences through.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dcs {
+    /// Parameters separated by `;`.
+    pub params: Vec<String>,
+    /// Intermediate characters (U+0020 to U+002F) in front of the final byte.
+    pub intermediates: String,
+    /// Character which ends the header.
+    pub final_byte: char,
+    /// Everything between the header and the string terminator.
+    /// Escaped ESCs (`ESC ESC`) are kept as they are.
+    pub data: String,
+}
+impl Dcs {
+    pub(crate) const START: char = 'P';
+
+    /// Parse the device control string following the introducer.
+    /// Returns `None` if the header is invalid or the string isn't terminated.
+    pub(crate) fn parse(
+        cursor: &mut CharCursor,
+        options: ParseOptions,
+        errors: &mut Vec<ParseErrorKind>,
+    ) -> Option<Self> {
+        let (params, intermediates, final_byte) = Csi::read_params(cursor);
+        let data = final_byte.and_then(|_| read_payload(cursor, options, false, true));
+        let (final_byte, data) = match (final_byte, data) {
+            (Some(final_byte), Some(data)) => (final_byte, data),
+            _ => {
+                errors.push(ParseErrorKind::UnterminatedSequence);
+                return None;
+            }
+        };
+
+        Some(Self {
+            params: params.iter().map(|&param| param.to_owned()).collect(),
+            intermediates: intermediates.to_owned(),
+            final_byte,
+            data: data.to_owned(),
+        })
+    }
+}
+impl Display for Dcs {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\u{001b}P{}{}{}{}\u{001b}\\",
+            self.params.join(";"),
+            self.intermediates,
+            self.final_byte,
+            self.data
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Option<Dcs> {
+        let s = s.replace("ST", "\u{001b}\\");
+        let mut cursor = CharCursor::new(&s);
+        cursor.read_char(Dcs::START)?;
+        Dcs::parse(&mut cursor, ParseOptions::default(), &mut Vec::new())
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            parse("P1;2$rdataST"),
+            Some(Dcs {
+                params: vec!["1".to_owned(), "2".to_owned()],
+                intermediates: "$".to_owned(),
+                final_byte: 'r',
+                data: "data".to_owned(),
+            })
+        );
+        assert_eq!(
+            parse("Ptmux;ST"),
+            Some(Dcs {
+                params: vec![],
+                intermediates: String::new(),
+                final_byte: 't',
+                data: "mux;".to_owned(),
+            })
+        );
+        // BEL doesn't terminate a device control string
+        assert_eq!(
+            parse("Pq#0\u{0007}ST").map(|dcs| dcs.data),
+            Some("#0\u{0007}".to_owned())
+        );
+        assert_eq!(
+            parse("Ptmux;\u{001b}\u{001b}[1m\u{001b}\u{001b}ST").map(|dcs| dcs.data),
+            Some("mux;\u{001b}\u{001b}[1m\u{001b}\u{001b}".to_owned())
+        );
+        assert_eq!(parse("Pqdata"), None);
+        assert_eq!(parse("Pqdata\u{001b}[1m"), None);
+    }
+}
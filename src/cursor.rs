@@ -41,7 +41,8 @@ impl<'a> CharCursor<'a> {
         self.peek() == Some(expected)
     }
 
-    fn read_if(&mut self, f: impl FnOnce(char) -> bool) -> Option<char> {
+    /// Read the next character and advance the cursor only if the given function returns `true` for it.
+    pub fn read_if(&mut self, f: impl FnOnce(char) -> bool) -> Option<char> {
         match self.peek() {
             Some(c) if f(c) => {
                 self.pos += c.len_utf8();
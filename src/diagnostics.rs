@@ -0,0 +1,161 @@
+use crate::sequences::{self, ParseOptions};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    num::{IntErrorKind, ParseIntError},
+    ops::Range,
+};
+
+/// Kind of problem with an escape sequence.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The sequence ends before its final byte or string terminator.
+    UnterminatedSequence,
+    /// A numeric parameter is too large.
+    IntegerOverflow,
+    /// A parameter isn't a number, is missing, or has an unsupported value.
+    InvalidParameter,
+    /// A colour component or palette index is larger than 255.
+    ColorOutOfRange,
+    /// SGR code which isn't supported.
+    UnknownSgr(usize),
+}
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnterminatedSequence => f.write_str("unterminated escape sequence"),
+            Self::IntegerOverflow => f.write_str("parameter is too large"),
+            Self::InvalidParameter => f.write_str("invalid parameter"),
+            Self::ColorOutOfRange => f.write_str("colour is out of range"),
+            Self::UnknownSgr(code) => write!(f, "unknown SGR code {}", code),
+        }
+    }
+}
+
+/// Problem with an escape sequence in the input.
+///
+/// Returned by [`validate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Byte range of the escape sequence in the input.
+    pub span: Range<usize>,
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+impl Error for ParseError {}
+
+/// Parse a numeric parameter.
+pub(crate) fn parse_number(s: &str) -> Result<usize, ParseErrorKind> {
+    s.parse().map_err(|err: ParseIntError| match err.kind() {
+        IntErrorKind::PosOverflow => ParseErrorKind::IntegerOverflow,
+        _ => ParseErrorKind::InvalidParameter,
+    })
+}
+
+/// Find all problems with the escape sequences in the given string.
+///
+/// The regular parser silently ignores these problems.
+/// A sequence can have more than one problem, in which case they all share the same span.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let errors = yew_ansi::validate("ok \u{001b}[38;2;300;0;0mcolour \u{001b}[1");
+/// assert_eq!(
+///     errors,
+///     vec![
+///         ParseError {
+///             kind: ParseErrorKind::ColorOutOfRange,
+///             span: 3..18,
+///         },
+///         ParseError {
+///             kind: ParseErrorKind::UnterminatedSequence,
+///             span: 25..28,
+///         },
+///     ]
+/// );
+/// ```
+pub fn validate(s: &str) -> Vec<ParseError> {
+    validate_with_options(s, ParseOptions::default())
+}
+
+/// Same as [`validate`] but with the given [`ParseOptions`].
+pub fn validate_with_options(s: &str, options: ParseOptions) -> Vec<ParseError> {
+    let mut errors = Vec::new();
+    let mut kinds = Vec::new();
+    let mut remaining = s;
+    loop {
+        let (pre, esc, post) =
+            sequences::read_next_sequence_with_errors(remaining, options, &mut kinds);
+        if esc.is_none() {
+            break;
+        }
+
+        let start = s.len() - remaining.len() + pre.len();
+        let span = start..s.len() - post.len();
+        errors.extend(kinds.drain(..).map(|kind| ParseError {
+            kind,
+            span: span.clone(),
+        }));
+        remaining = post;
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(s: &str) -> Vec<ParseErrorKind> {
+        validate(s).into_iter().map(|error| error.kind).collect()
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(
+            validate("\u{001b}[1;38;5;196m\u{001b}]0;title\u{0007}\u{001b}[2K"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            kinds("\u{001b}[38;5;256m"),
+            vec![ParseErrorKind::ColorOutOfRange]
+        );
+        assert_eq!(
+            kinds("\u{001b}[38:2::0:0:1000m"),
+            vec![ParseErrorKind::ColorOutOfRange]
+        );
+        assert_eq!(
+            kinds("\u{001b}[99999999999999999999999A"),
+            vec![ParseErrorKind::IntegerOverflow]
+        );
+        assert_eq!(
+            kinds("\u{001b}[1;73;3;74m"),
+            vec![
+                ParseErrorKind::UnknownSgr(73),
+                ParseErrorKind::UnknownSgr(74)
+            ]
+        );
+        assert_eq!(
+            kinds("\u{001b}[38;2;1m"),
+            vec![ParseErrorKind::InvalidParameter]
+        );
+        assert_eq!(
+            kinds("\u{001b}]0;title\u{001b}[1m"),
+            vec![ParseErrorKind::UnterminatedSequence]
+        );
+        assert_eq!(
+            kinds("\u{001b}[1\n"),
+            vec![ParseErrorKind::UnterminatedSequence]
+        );
+    }
+}
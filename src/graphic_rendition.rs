@@ -1,13 +1,17 @@
-use crate::style::{ClassStyle, StyleBuilder};
+use crate::{
+    diagnostics::{self, ParseErrorKind},
+    style::{ClassStyle, StyleBuilder},
+};
 use std::{
     borrow::Borrow,
-    convert::TryFrom,
     fmt::{self, Display, Formatter},
-    iter,
+    iter::{self, Peekable},
+    slice,
 };
 
 /// The 8 colors defined by the original specification.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorName {
     Black,
     Red,
@@ -26,6 +30,11 @@ impl ColorName {
             .copied()
     }
 
+    /// Get the offset of the colour in the colour codes (`30 + offset`, `40 + offset`, ...).
+    fn offset(self) -> usize {
+        self as usize
+    }
+
     /// Get the 24-bit colour code.
     pub fn rgb(self, bright: bool) -> u32 {
         use ColorName::*;
@@ -78,17 +87,74 @@ impl Display for ColorName {
     }
 }
 
+/// Style of an underline.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnderlineStyle {
+    #[default]
+    None,
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+impl UnderlineStyle {
+    /// Get the style from the sub-parameter of `SGR 4`.
+    fn from_param(n: usize) -> Option<Self> {
+        use UnderlineStyle::*;
+        [None, Single, Double, Curly, Dotted, Dashed]
+            .get(n)
+            .copied()
+    }
+
+    /// Get the sub-parameter of `SGR 4` for this style.
+    fn param(self) -> usize {
+        self as usize
+    }
+}
+
+/// Which colour a colour SGR parameter sets.
+#[derive(Clone, Copy, Debug)]
+enum ColorLayer {
+    Fg,
+    Bg,
+    Underline,
+}
+
 /// Select Graphic Rendition parameter.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Sgr {
     Reset,
     Bold,
+    /// Normal intensity, turns off both bold and faint.
     BoldOff,
+    /// Decreased intensity.
+    Faint,
     Italic,
     ItalicOff,
     Underline,
+    /// Underline with the given style (`SGR 4:n`).
+    ///
+    /// `SGR 21` is parsed as a double underline.
+    StyledUnderline(UnderlineStyle),
+    /// Turns off the underline regardless of its style.
     UnderlineOff,
+    Blink,
+    /// Same as [`Sgr::Blink`] but faster. Most terminals don't distinguish the two.
+    RapidBlink,
+    BlinkOff,
+    /// Swap the foreground and background colours.
+    Inverse,
+    InverseOff,
+    Hidden,
+    HiddenOff,
+    Strikethrough,
+    StrikethroughOff,
+    Overline,
+    OverlineOff,
     ColorFgRgb(u32),
     ColorFgName(ColorName),
     ColorFgNameBright(ColorName),
@@ -97,106 +163,299 @@ pub enum Sgr {
     ColorBgName(ColorName),
     ColorBgNameBright(ColorName),
     ResetColorBg,
+    ColorUnderlineRgb(u32),
+    ColorUnderlineName(ColorName),
+    ColorUnderlineNameBright(ColorName),
+    /// Use the foreground colour for the underline.
+    ResetColorUnderline,
 }
 impl Sgr {
-    fn from_color_code(code: usize, background: bool, bright: bool) -> Option<Self> {
+    fn from_color_code(
+        code: usize,
+        layer: ColorLayer,
+        bright: bool,
+    ) -> Result<Self, ParseErrorKind> {
         use Sgr::*;
-        let color = ColorName::from_code(code)?;
-        let sgr = match (background, bright) {
-            (false, false) => ColorFgName(color),
-            (false, true) => ColorFgNameBright(color),
-            (true, false) => ColorBgName(color),
-            (true, true) => ColorBgNameBright(color),
+        let color = ColorName::from_code(code).ok_or(ParseErrorKind::InvalidParameter)?;
+        let sgr = match (layer, bright) {
+            (ColorLayer::Fg, false) => ColorFgName(color),
+            (ColorLayer::Fg, true) => ColorFgNameBright(color),
+            (ColorLayer::Bg, false) => ColorBgName(color),
+            (ColorLayer::Bg, true) => ColorBgNameBright(color),
+            (ColorLayer::Underline, false) => ColorUnderlineName(color),
+            (ColorLayer::Underline, true) => ColorUnderlineNameBright(color),
         };
-        Some(sgr)
+        Ok(sgr)
     }
 
-    fn from_rgb(r: usize, g: usize, b: usize, background: bool) -> Option<Self> {
-        let rgb = u32::try_from((r << 16) + (g << 8) + b).ok()?;
-        let sgr = if background {
-            Self::ColorBgRgb(rgb)
-        } else {
-            Self::ColorFgRgb(rgb)
+    fn from_rgb(r: usize, g: usize, b: usize, layer: ColorLayer) -> Result<Self, ParseErrorKind> {
+        if r > 0xFF || g > 0xFF || b > 0xFF {
+            return Err(ParseErrorKind::ColorOutOfRange);
+        }
+        let rgb = ((r << 16) + (g << 8) + b) as u32;
+        let sgr = match layer {
+            ColorLayer::Fg => Self::ColorFgRgb(rgb),
+            ColorLayer::Bg => Self::ColorBgRgb(rgb),
+            ColorLayer::Underline => Self::ColorUnderlineRgb(rgb),
         };
-        Some(sgr)
+        Ok(sgr)
     }
 
-    fn color_rgb(mut params: impl Iterator<Item = usize>, background: bool) -> Option<Self> {
-        match params.next()? {
+    fn color_rgb(
+        mut params: impl Iterator<Item = usize>,
+        layer: ColorLayer,
+    ) -> Result<Self, ParseErrorKind> {
+        let mut next = || params.next().ok_or(ParseErrorKind::InvalidParameter);
+        match next()? {
             2 => {
-                let (r, g, b) = (params.next()?, params.next()?, params.next()?);
-                Self::from_rgb(r, g, b, background)
+                let (r, g, b) = (next()?, next()?, next()?);
+                Self::from_rgb(r, g, b, layer)
             }
-            5 => {
-                let n = params.next()?;
-                match n {
-                    0..=7 => Self::from_color_code(n, background, false),
-                    8..=15 => Self::from_color_code(n - 8, background, true),
-                    16..=231 => {
-                        // palette represents a 6 * 6 * 6 cube where the three
-                        // dimensions represent r, g, and b.
-                        // Comments here assume a 2D representation of the cube.
-                        // See: https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit
-                        const ROWS: usize = 6;
-                        const COLUMNS: usize = 36;
-                        const STEP_SIZE: usize = 0xFF / 6;
-
-                        let n = n - 16;
-                        // increases with each row
-                        let r = (n / COLUMNS) * STEP_SIZE;
-                        // g is constant for each 6 * 6 block
-                        let g = ((n % COLUMNS) / ROWS) * STEP_SIZE;
-                        // increases with each column but resets every 6.
-                        let b = (n % ROWS) * STEP_SIZE;
-                        Self::from_rgb(r, g, b, background)
-                    }
-                    232..=255 => {
-                        const STEP_SIZE: usize = 0xFF / 24;
-                        let n = n - 232;
-                        Self::from_rgb(n * STEP_SIZE, n * STEP_SIZE, n * STEP_SIZE, background)
-                    }
-                    _ => None,
-                }
+            5 => Self::from_palette(next()?, layer),
+            _ => Err(ParseErrorKind::InvalidParameter),
+        }
+    }
+
+    /// Parse the colon separated sub-parameters of an extended colour (ITU T.416).
+    /// Unlike the semicolon form, the RGB form has an (optional) colour space id before the components.
+    fn color_sub_params(sub: &[usize], layer: ColorLayer) -> Result<Self, ParseErrorKind> {
+        match *sub {
+            [2, r, g, b] | [2, _, r, g, b, ..] => Self::from_rgb(r, g, b, layer),
+            [5, n] => Self::from_palette(n, layer),
+            _ => Err(ParseErrorKind::InvalidParameter),
+        }
+    }
+
+    /// Get the colour at index `n` of the 256 colour palette.
+    fn from_palette(n: usize, layer: ColorLayer) -> Result<Self, ParseErrorKind> {
+        match n {
+            0..=7 => Self::from_color_code(n, layer, false),
+            8..=15 => Self::from_color_code(n - 8, layer, true),
+            16..=231 => {
+                // palette represents a 6 * 6 * 6 cube where the three
+                // dimensions represent r, g, and b.
+                // Comments here assume a 2D representation of the cube.
+                // See: https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit
+                const ROWS: usize = 6;
+                const COLUMNS: usize = 36;
+                const STEP_SIZE: usize = 0xFF / 6;
+
+                let n = n - 16;
+                // increases with each row
+                let r = (n / COLUMNS) * STEP_SIZE;
+                // g is constant for each 6 * 6 block
+                let g = ((n % COLUMNS) / ROWS) * STEP_SIZE;
+                // increases with each column but resets every 6.
+                let b = (n % ROWS) * STEP_SIZE;
+                Self::from_rgb(r, g, b, layer)
+            }
+            232..=255 => {
+                const STEP_SIZE: usize = 0xFF / 24;
+                let n = n - 232;
+                Self::from_rgb(n * STEP_SIZE, n * STEP_SIZE, n * STEP_SIZE, layer)
             }
-            _ => None,
+            _ => Err(ParseErrorKind::ColorOutOfRange),
+        }
+    }
+
+    /// Parse a SGR parameter with colon separated sub-parameters.
+    fn from_sub_params(code: usize, sub: &[usize]) -> Result<Self, ParseErrorKind> {
+        use Sgr::*;
+        match (code, sub) {
+            (4, [n]) => UnderlineStyle::from_param(*n)
+                .map(StyledUnderline)
+                .ok_or(ParseErrorKind::InvalidParameter),
+            (38, sub) => Self::color_sub_params(sub, ColorLayer::Fg),
+            (48, sub) => Self::color_sub_params(sub, ColorLayer::Bg),
+            (58, sub) => Self::color_sub_params(sub, ColorLayer::Underline),
+            _ => Err(ParseErrorKind::UnknownSgr(code)),
         }
     }
 
     /// Parse a single SGR parameter from the parameters.
     /// This will only consume as many items from `params` as required to complete the SGR.
-    fn from_params(mut params: impl Iterator<Item = usize>) -> Option<Self> {
+    fn from_params(params: &mut Peekable<slice::Iter<Vec<usize>>>) -> Result<Self, ParseErrorKind> {
         use Sgr::*;
-        let code = params.next()?;
-        Some(match code {
+        let (&code, sub) = params
+            .next()
+            .and_then(|param| param.split_first())
+            .ok_or(ParseErrorKind::InvalidParameter)?;
+        if !sub.is_empty() {
+            return Self::from_sub_params(code, sub);
+        }
+
+        // the semicolon form of the extended colours uses the following parameters
+        let mut params = iter::from_fn(|| {
+            params
+                .next_if(|param| param.len() == 1)
+                .map(|param| param[0])
+        });
+        Ok(match code {
             0 => Reset,
             1 => Bold,
+            2 => Faint,
             3 => Italic,
             4 => Underline,
+            5 => Blink,
+            6 => RapidBlink,
+            7 => Inverse,
+            8 => Hidden,
+            9 => Strikethrough,
+            21 => StyledUnderline(UnderlineStyle::Double),
             22 => BoldOff,
             23 => ItalicOff,
             24 => UnderlineOff,
-            30..=37 => ColorFgName(ColorName::from_code(code)?),
-            38 => Self::color_rgb(params, false)?,
+            25 => BlinkOff,
+            27 => InverseOff,
+            28 => HiddenOff,
+            29 => StrikethroughOff,
+            30..=37 => Self::from_color_code(code, ColorLayer::Fg, false)?,
+            38 => Self::color_rgb(&mut params, ColorLayer::Fg)?,
             39 => ResetColorFg,
-            40..=47 => ColorBgName(ColorName::from_code(code)?),
-            48 => Self::color_rgb(params, true)?,
+            40..=47 => Self::from_color_code(code, ColorLayer::Bg, false)?,
+            48 => Self::color_rgb(&mut params, ColorLayer::Bg)?,
             49 => ResetColorBg,
-            90..=97 => ColorFgNameBright(ColorName::from_code(code)?),
-            100..=107 => ColorBgNameBright(ColorName::from_code(code)?),
-            _ => return None,
+            53 => Overline,
+            55 => OverlineOff,
+            58 => Self::color_rgb(&mut params, ColorLayer::Underline)?,
+            59 => ResetColorUnderline,
+            90..=97 => Self::from_color_code(code, ColorLayer::Fg, true)?,
+            100..=107 => Self::from_color_code(code, ColorLayer::Bg, true)?,
+            _ => return Err(ParseErrorKind::UnknownSgr(code)),
         })
     }
 }
 
+impl Sgr {
+    /// Write the parameter without the surrounding control sequence.
+    ///
+    /// If `compact` is true, the reset is written as an empty parameter.
+    fn fmt_param(&self, f: &mut Formatter, compact: bool) -> fmt::Result {
+        use Sgr::*;
+        let rgb = |f: &mut Formatter, code: usize, rgb: u32| {
+            let [_, r, g, b] = rgb.to_be_bytes();
+            write!(f, "{};2;{};{};{}", code, r, g, b)
+        };
+        let code = match self {
+            Reset if compact => return Ok(()),
+            Reset => 0,
+            Bold => 1,
+            Faint => 2,
+            Italic => 3,
+            Underline => 4,
+            Blink => 5,
+            RapidBlink => 6,
+            Inverse => 7,
+            Hidden => 8,
+            Strikethrough => 9,
+            BoldOff => 22,
+            ItalicOff => 23,
+            UnderlineOff => 24,
+            BlinkOff => 25,
+            InverseOff => 27,
+            HiddenOff => 28,
+            StrikethroughOff => 29,
+            ResetColorFg => 39,
+            ResetColorBg => 49,
+            Overline => 53,
+            OverlineOff => 55,
+            ResetColorUnderline => 59,
+            StyledUnderline(style) => return write!(f, "4:{}", style.param()),
+            ColorFgName(name) => 30 + name.offset(),
+            ColorFgNameBright(name) => 90 + name.offset(),
+            ColorBgName(name) => 40 + name.offset(),
+            ColorBgNameBright(name) => 100 + name.offset(),
+            // underline colours don't have their own codes for the named colours
+            ColorUnderlineName(name) => return write!(f, "58;5;{}", name.offset()),
+            ColorUnderlineNameBright(name) => return write!(f, "58;5;{}", 8 + name.offset()),
+            ColorFgRgb(color) => return rgb(f, 38, *color),
+            ColorBgRgb(color) => return rgb(f, 48, *color),
+            ColorUnderlineRgb(color) => return rgb(f, 58, *color),
+        };
+        write!(f, "{}", code)
+    }
+}
+/// Writes the SGR as a control sequence.
+///
+/// ```
+/// # use yew_ansi::*;
+/// assert_eq!(Sgr::ColorFgRgb(0xff8000).to_string(), "\u{001b}[38;2;255;128;0m");
+/// ```
+impl Display for Sgr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("\u{001b}[")?;
+        self.fmt_param(f, false)?;
+        f.write_str("m")
+    }
+}
+
+/// Writes the parameters of a SGR control sequence, separated by `;`.
+pub(crate) struct SgrParams<'a>(pub &'a [Sgr]);
+impl Display for SgrParams<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fmt_params(f, self.0, false)
+    }
+}
+
+/// Same as [`SgrParams`], but the reset is written as an empty parameter.
+pub(crate) struct CompactSgrParams<'a>(pub &'a [Sgr]);
+impl Display for CompactSgrParams<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fmt_params(f, self.0, true)
+    }
+}
+
+fn fmt_params(f: &mut Formatter, sgrs: &[Sgr], compact: bool) -> fmt::Result {
+    for (i, sgr) in sgrs.iter().enumerate() {
+        if i > 0 {
+            f.write_str(";")?;
+        }
+        sgr.fmt_param(f, compact)?;
+    }
+    Ok(())
+}
+
 /// Parse all SGR parameters in the given parameters.
-/// This only consumes as many items from `params` as can be parsed by [`Sgr`].
-pub(crate) fn parse_sgrs(mut params: impl Iterator<Item = usize>) -> Vec<Sgr> {
-    iter::from_fn(|| Sgr::from_params(&mut params)).collect()
+/// Each parameter may contain colon separated sub-parameters, empty (sub-)parameters default to 0.
+/// Parameters which aren't supported are skipped and reported in `errors`.
+///
+/// Returns `None` if a parameter isn't a number.
+pub(crate) fn parse_sgrs(params: &[&str], errors: &mut Vec<ParseErrorKind>) -> Option<Vec<Sgr>> {
+    // no parameters at all is the same as a single empty one, which resets everything
+    let params = if params.is_empty() { &[""] } else { params };
+    let params = params
+        .iter()
+        .map(|param| {
+            param
+                .split(':')
+                .map(|sub| {
+                    if sub.is_empty() {
+                        Ok(0)
+                    } else {
+                        diagnostics::parse_number(sub)
+                    }
+                })
+                .collect::<Result<Vec<usize>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|kind| errors.push(kind))
+        .ok()?;
+    let mut params = params.iter().peekable();
+    let mut sgrs = Vec::new();
+    while params.peek().is_some() {
+        match Sgr::from_params(&mut params) {
+            Ok(sgr) => sgrs.push(sgr),
+            Err(kind) => errors.push(kind),
+        }
+    }
+    Some(sgrs)
 }
 
 /// Describes the color effect of multiple SGR parameters.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorEffect {
+    #[default]
     None,
     Name(ColorName),
     NameBright(ColorName),
@@ -213,19 +472,35 @@ impl ColorEffect {
         }
     }
 }
-impl Default for ColorEffect {
-    fn default() -> Self {
-        Self::None
+impl ColorEffect {
+    /// Get the SGR which sets this colour on the given layer.
+    fn to_sgr(&self, layer: ColorLayer) -> Sgr {
+        use Sgr::*;
+        match (layer, self) {
+            (ColorLayer::Fg, Self::None) => ResetColorFg,
+            (ColorLayer::Fg, Self::Name(name)) => ColorFgName(*name),
+            (ColorLayer::Fg, Self::NameBright(name)) => ColorFgNameBright(*name),
+            (ColorLayer::Fg, Self::Rgb(rgb)) => ColorFgRgb(*rgb),
+            (ColorLayer::Bg, Self::None) => ResetColorBg,
+            (ColorLayer::Bg, Self::Name(name)) => ColorBgName(*name),
+            (ColorLayer::Bg, Self::NameBright(name)) => ColorBgNameBright(*name),
+            (ColorLayer::Bg, Self::Rgb(rgb)) => ColorBgRgb(*rgb),
+            (ColorLayer::Underline, Self::None) => ResetColorUnderline,
+            (ColorLayer::Underline, Self::Name(name)) => ColorUnderlineName(*name),
+            (ColorLayer::Underline, Self::NameBright(name)) => ColorUnderlineNameBright(*name),
+            (ColorLayer::Underline, Self::Rgb(rgb)) => ColorUnderlineRgb(*rgb),
+        }
     }
 }
-
 impl From<&Sgr> for ColorEffect {
     fn from(sgr: &Sgr) -> Self {
         use Sgr::*;
         match sgr {
-            ColorFgRgb(rgb) | ColorBgRgb(rgb) => Self::Rgb(*rgb),
-            ColorFgName(name) | ColorBgName(name) => Self::Name(*name),
-            ColorFgNameBright(name) | ColorBgNameBright(name) => Self::NameBright(*name),
+            ColorFgRgb(rgb) | ColorBgRgb(rgb) | ColorUnderlineRgb(rgb) => Self::Rgb(*rgb),
+            ColorFgName(name) | ColorBgName(name) | ColorUnderlineName(name) => Self::Name(*name),
+            ColorFgNameBright(name) | ColorBgNameBright(name) | ColorUnderlineNameBright(name) => {
+                Self::NameBright(*name)
+            }
             _ => Self::None,
         }
     }
@@ -233,14 +508,26 @@ impl From<&Sgr> for ColorEffect {
 
 /// Describes the effect that multiple SGR parameters have on text.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct SgrEffect {
     pub bold: bool,
+    pub faint: bool,
     pub italic: bool,
-    pub underline: bool,
+    pub underline: UnderlineStyle,
+    /// Either slow or rapid blinking.
+    pub blink: bool,
+    /// Whether the foreground and background colours are swapped.
+    pub inverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
     /// Foreground colour
     pub fg: ColorEffect,
     /// Background colour
     pub bg: ColorEffect,
+    /// Underline colour, [`ColorEffect::None`] uses the foreground colour.
+    pub underline_color: ColorEffect,
 }
 impl SgrEffect {
     fn reset(&mut self) {
@@ -254,17 +541,38 @@ impl SgrEffect {
         match sgr {
             Reset => self.reset(),
             Bold => self.bold = true,
-            BoldOff => self.bold = false,
+            BoldOff => {
+                self.bold = false;
+                self.faint = false;
+            }
+            Faint => self.faint = true,
             Italic => self.italic = true,
             ItalicOff => self.italic = false,
-            Underline => self.underline = true,
-            UnderlineOff => self.underline = false,
+            Underline => self.underline = UnderlineStyle::Single,
+            StyledUnderline(style) => self.underline = *style,
+            UnderlineOff => self.underline = UnderlineStyle::None,
+            Blink | RapidBlink => self.blink = true,
+            BlinkOff => self.blink = false,
+            Inverse => self.inverse = true,
+            InverseOff => self.inverse = false,
+            Hidden => self.hidden = true,
+            HiddenOff => self.hidden = false,
+            Strikethrough => self.strikethrough = true,
+            StrikethroughOff => self.strikethrough = false,
+            Overline => self.overline = true,
+            OverlineOff => self.overline = false,
             ColorFgRgb(_) | ColorFgName(_) | ColorFgNameBright(_) | ResetColorFg => {
                 self.fg = ColorEffect::from(sgr);
             }
             ColorBgRgb(_) | ColorBgName(_) | ColorBgNameBright(_) | ResetColorBg => {
                 self.bg = ColorEffect::from(sgr);
             }
+            ColorUnderlineRgb(_)
+            | ColorUnderlineName(_)
+            | ColorUnderlineNameBright(_)
+            | ResetColorUnderline => {
+                self.underline_color = ColorEffect::from(sgr);
+            }
         }
     }
 
@@ -275,19 +583,136 @@ impl SgrEffect {
         }
     }
 
+    /// Get the SGR parameters which change this effect into `to`, without resetting first.
+    fn changes_to(&self, to: &Self) -> Vec<Sgr> {
+        use Sgr::*;
+        let mut sgrs = Vec::new();
+        // there's no way to turn off bold and faint separately
+        if (self.bold && !to.bold) || (self.faint && !to.faint) {
+            sgrs.push(BoldOff);
+            sgrs.extend(to.bold.then_some(Bold));
+            sgrs.extend(to.faint.then_some(Faint));
+        } else {
+            sgrs.extend((to.bold && !self.bold).then_some(Bold));
+            sgrs.extend((to.faint && !self.faint).then_some(Faint));
+        }
+        if self.underline != to.underline {
+            sgrs.push(match to.underline {
+                UnderlineStyle::None => UnderlineOff,
+                UnderlineStyle::Single => Underline,
+                style => StyledUnderline(style),
+            });
+        }
+        for (from, to, on, off) in [
+            (self.italic, to.italic, Italic, ItalicOff),
+            (self.blink, to.blink, Blink, BlinkOff),
+            (self.inverse, to.inverse, Inverse, InverseOff),
+            (self.hidden, to.hidden, Hidden, HiddenOff),
+            (
+                self.strikethrough,
+                to.strikethrough,
+                Strikethrough,
+                StrikethroughOff,
+            ),
+            (self.overline, to.overline, Overline, OverlineOff),
+        ] {
+            if from != to {
+                sgrs.push(if to { on } else { off });
+            }
+        }
+        for (from, to, layer) in [
+            (&self.fg, &to.fg, ColorLayer::Fg),
+            (&self.bg, &to.bg, ColorLayer::Bg),
+            (
+                &self.underline_color,
+                &to.underline_color,
+                ColorLayer::Underline,
+            ),
+        ] {
+            if from != to {
+                sgrs.push(to.to_sgr(layer));
+            }
+        }
+        sgrs
+    }
+
+    /// Get the shortest list of SGR parameters which changes this effect into `to`.
+    ///
+    /// This either changes the attributes which differ, or resets everything and sets the ones of `to`.
+    /// The list is empty if the effects are the same.
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let bold = SgrEffect { bold: true, ..Default::default() };
+    /// let red = SgrEffect { fg: ColorEffect::Name(ColorName::Red), ..bold.clone() };
+    /// assert_eq!(bold.transition(&red), vec![Sgr::ColorFgName(ColorName::Red)]);
+    /// assert_eq!(red.transition(&SgrEffect::default()), vec![Sgr::Reset]);
+    /// assert_eq!(Csi::Sgr(bold.transition(&red)).to_string(), "\u{001b}[31m");
+    /// ```
+    pub fn transition(&self, to: &Self) -> Vec<Sgr> {
+        self.shortest_transition(to, |sgrs| SgrParams(sgrs).to_string().len())
+    }
+
+    /// Same as [`SgrEffect::transition`], but the length is measured as written by [`CompactSgrParams`].
+    pub(crate) fn compact_transition(&self, to: &Self) -> Vec<Sgr> {
+        self.shortest_transition(to, |sgrs| CompactSgrParams(sgrs).to_string().len())
+    }
+
+    fn shortest_transition(&self, to: &Self, len: impl Fn(&[Sgr]) -> usize) -> Vec<Sgr> {
+        let changes = self.changes_to(to);
+        if changes.is_empty() {
+            return changes;
+        }
+
+        let mut reset = vec![Sgr::Reset];
+        reset.extend(Self::default().changes_to(to));
+        if len(&reset) < len(&changes) {
+            reset
+        } else {
+            changes
+        }
+    }
+
+    /// Build the class and style for this effect.
+    ///
+    /// The attributes are applied before the colours.
+    /// If the effect is inverse, the colours passed to the builder are already swapped.
     pub fn to_class_style<B: StyleBuilder>(&self) -> ClassStyle {
         let mut builder = B::default();
         if self.bold {
             builder.bold();
         }
+        if self.faint {
+            builder.faint();
+        }
         if self.italic {
             builder.italic();
         }
-        if self.underline {
-            builder.underline();
+        if self.underline != UnderlineStyle::None {
+            builder.underline_style(self.underline);
+            builder.underline_color(&self.underline_color);
+        }
+        if self.blink {
+            builder.blink();
+        }
+        if self.hidden {
+            builder.hidden();
+        }
+        if self.strikethrough {
+            builder.strikethrough();
+        }
+        if self.overline {
+            builder.overline();
+        }
+
+        if self.inverse {
+            builder.inverse();
+            builder.fg_color(&self.bg);
+            builder.bg_color(&self.fg);
+        } else {
+            builder.fg_color(&self.fg);
+            builder.bg_color(&self.bg);
         }
-        builder.fg_color(&self.fg);
-        builder.bg_color(&self.bg);
 
         builder.finish()
     }
@@ -29,19 +29,92 @@
 //! to iterate over text segments along with their [`SgrEffect`].
 //!
 //! If you need more control, use [`get_markers`] to iterate over the raw [`Escape`] codes in the text.
+//! Both have `*_with_spans` variants which also yield the byte range each item was read from.
+//!
+//! Use [`get_ansi_lines`] to split the segments into lines which each know the effect they start with.
+//!
+//! Use [`get_edited_segments`] to apply carriage returns the way a terminal would.
+//! Consecutive segments which look the same can be joined using [`merge_segments`].
+//! Text that moves the cursor around can be interpreted using a [`Screen`].
+//!
+//! Raw output which might not be valid UTF-8 can be parsed using [`get_markers_bytes`].
+//!
+//! Text which arrives in chunks (for example over a network connection) can be parsed using an [`AnsiParser`].
+//!
+//! Use [`validate`] to find malformed escape sequences, which are otherwise ignored.
+//!
+//! To get the plain text, remove all escape sequences using [`strip`] or a [`StripWriter`].
+//! Use [`visible_width`], [`pad_to`], and [`truncate_to`] to align text containing escape sequences.
+//! Long lines can be wrapped at a fixed width using [`get_wrapped_lines`].
+//!
+//! Parsed sequences can be written back as text using their [`Display`](std::fmt::Display) implementation,
+//! and [`SgrEffect::transition`] gets the SGR parameters which change one effect into another.
+//! Use [`optimize`] to re-encode text using as few SGR bytes as possible.
+//!
+//! The `*_with_options` variants of these functions take [`ParseOptions`],
+//! for example to recognise 8-bit C1 control codes.
+//!
+//! # Serde
+//!
+//! With the `serde` feature enabled, the parsed types ([`Marker`], [`Escape`], [`Sgr`], [`SgrEffect`], etc.)
+//! implement `Serialize` and `Deserialize`.
+//! [`Marker`] borrows from the input, so deserialize it as an [`OwnedMarker`] instead.
+//! [`AnsiLine`] and [`WrappedLine`] borrow from the input as well and can only be serialized.
+//! The text of a deserialized [`Segment`] or [`ByteMarker`] is always owned,
+//! and hyperlinks or embeds which were shared between segments are no longer shared.
+//!
+//! The wire format is the default one of serde, changing it is a breaking change:
+//!
+//! - Structs are maps using the field names as keys.
+//! - Enums are externally tagged using the variant names.
+//!   Unit variants are just their name, so `Sgr::ColorFgName(ColorName::Red)` is `{"ColorFgName":"Red"}` in JSON.
+//! - Colours are `0xRRGGBB` numbers.
+//! - Missing fields of an [`SgrEffect`] have their default value.
+//!
+//! New variants can be added to the non-exhaustive enums, which older versions can't deserialize.
+
+use std::ops::Range;
 
+pub use bytes::*;
+pub use control_string::Dcs;
 pub use cursor::CharCursor;
+pub use diagnostics::*;
 pub use graphic_rendition::*;
+pub use line_edit::*;
+pub use lines::*;
+pub use merge::*;
+pub use optimize::*;
+pub use osc::*;
+pub use screen::*;
 pub use sequences::*;
+pub use sixel::*;
+pub use stream::*;
+pub use strip::*;
 pub use style::*;
+pub use width::*;
+pub use wrap::*;
 
 #[cfg(feature = "yew")]
 pub use yew_component::*;
 
+mod bytes;
+mod control_string;
 mod cursor;
+mod diagnostics;
 mod graphic_rendition;
+mod line_edit;
+mod lines;
+mod merge;
+mod optimize;
+mod osc;
+mod screen;
 mod sequences;
+mod sixel;
+mod stream;
+mod strip;
 mod style;
+mod width;
+mod wrap;
 #[cfg(feature = "yew")]
 mod yew_component;
 
@@ -53,33 +126,60 @@ mod yew_component;
 #[must_use = "iterators are lazy and do nothing unless consumed"]
 #[derive(Clone, Debug)]
 pub struct SgrSegmentIter<'a> {
-    markers: MarkerIter<'a>,
+    markers: SpannedMarkerIter<'a>,
     effect: SgrEffect,
 }
 impl<'a> SgrSegmentIter<'a> {
     fn new(s: &'a str) -> Self {
         Self {
-            markers: get_markers(s),
+            markers: get_markers_with_spans(s),
             effect: SgrEffect::default(),
         }
     }
-}
-impl<'a> Iterator for SgrSegmentIter<'a> {
-    type Item = (SgrEffect, &'a str);
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Also yield the byte range of each text segment in the string.
+    pub fn with_spans(self) -> SpannedSgrSegmentIter<'a> {
+        SpannedSgrSegmentIter(self)
+    }
+
+    fn next_spanned(&mut self) -> Option<(SgrEffect, &'a str, Range<usize>)> {
         loop {
             match self.markers.next()? {
-                Marker::Text(text) => {
-                    return Some((self.effect.clone(), text));
+                (Marker::Text(text), span) => {
+                    return Some((self.effect.clone(), text, span));
                 }
-                Marker::Sequence(Escape::Csi(Csi::Sgr(sgrs))) => {
+                (Marker::Sequence(Escape::Csi(Csi::Sgr(sgrs))), _) => {
                     self.effect.apply_sgrs(sgrs);
                 }
+                (Marker::Sequence(_), _) => {}
             }
         }
     }
 }
+impl<'a> Iterator for SgrSegmentIter<'a> {
+    type Item = (SgrEffect, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|(effect, text, _)| (effect, text))
+    }
+}
+
+/// Iterator over the SGR segments in a string slice along with their byte range.
+///
+/// Each item is a tuple containing the [`SgrEffect`], the [`&str`][str] it applies to,
+/// and the [`Range`] of the string the text was taken from.
+///
+/// Returned by [`get_sgr_segments_with_spans`] and [`SgrSegmentIter::with_spans`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct SpannedSgrSegmentIter<'a>(SgrSegmentIter<'a>);
+impl<'a> Iterator for SpannedSgrSegmentIter<'a> {
+    type Item = (SgrEffect, &'a str, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_spanned()
+    }
+}
 
 /// Create an iterator which iterates over SGR segments in a string slice.
 /// Each item consists of a [`SgrEffect`] and the corresponding text slice it applies to.
@@ -109,6 +209,20 @@ impl<'a> Iterator for SgrSegmentIter<'a> {
 ///     ))
 /// );
 /// ```
-pub fn get_sgr_segments(s: &str) -> SgrSegmentIter {
+pub fn get_sgr_segments(s: &str) -> SgrSegmentIter<'_> {
     SgrSegmentIter::new(s)
 }
+
+/// Same as [`get_sgr_segments`] but also yields the byte range of each text segment.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let s = "a\u{001b}[1mb";
+/// let spans = yew_ansi::get_sgr_segments_with_spans(s)
+///     .map(|(_, text, span)| (text, span))
+///     .collect::<Vec<_>>();
+/// assert_eq!(spans, vec![("a", 0..1), ("b", 5..6)]);
+/// ```
+pub fn get_sgr_segments_with_spans(s: &str) -> SpannedSgrSegmentIter<'_> {
+    get_sgr_segments(s).with_spans()
+}
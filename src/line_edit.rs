@@ -0,0 +1,389 @@
+use crate::{
+    graphic_rendition::SgrEffect,
+    merge::MergedSegmentIter,
+    osc::{Hyperlink, Osc},
+    screen::{self, Cell, Embed, Line, Screen, Segment},
+    sequences::{self, Csi, Escape, Marker, MarkerIter, ParseOptions},
+    sixel::SixelImage,
+};
+use std::{collections::VecDeque, mem, rc::Rc};
+
+/// Characters which edit the current line instead of being displayed.
+const EDIT_CHARS: &[char] = &['\r', '\u{0008}'];
+
+#[derive(Clone, Debug)]
+enum LinePart<'a> {
+    Text(SgrEffect, Option<Rc<Hyperlink>>, &'a str),
+    /// Control sequence which only affects the current line.
+    Csi(Csi, SgrEffect),
+}
+
+/// Iterator over the SGR segments in a string slice with line editing applied.
+///
+/// Each item is a [`Segment`] which also carries the active hyperlink.
+/// Lines which aren't edited are borrowed from the input.
+///
+/// Returned by [`get_edited_segments`] and [`get_edited_segments_with_options`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct EditedSegmentIter<'a> {
+    markers: MarkerIter<'a>,
+    effect: SgrEffect,
+    link: Option<Rc<Hyperlink>>,
+    /// Parts of the current line.
+    line: Vec<LinePart<'a>>,
+    /// Whether the current line contains any of the [`EDIT_CHARS`] or control sequences.
+    edited: bool,
+    ready: VecDeque<Segment<'a>>,
+}
+impl<'a> EditedSegmentIter<'a> {
+    fn new(s: &'a str, options: ParseOptions) -> Self {
+        Self {
+            markers: sequences::get_markers_with_options(s, options),
+            effect: SgrEffect::default(),
+            link: None,
+            line: Vec::new(),
+            edited: false,
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Join consecutive segments which look the same.
+    ///
+    /// See [`merge_segments`](crate::merge_segments).
+    pub fn merged(self) -> MergedSegmentIter<'a, Self> {
+        crate::merge_segments(self)
+    }
+
+    fn push_text(&mut self, text: &'a str) {
+        let (head, tail) = match text.rfind('\n') {
+            Some(index) => text.split_at(index + 1),
+            None => ("", text),
+        };
+
+        if !head.is_empty() {
+            if self.edited || head.contains(EDIT_CHARS) {
+                for line in head.split_inclusive('\n') {
+                    self.push_line_part(line);
+                    self.finish_line();
+                }
+            } else {
+                // fast path: the lines are complete and none of them are edited
+                self.push_line_part(head);
+                self.finish_line();
+            }
+        }
+
+        if !tail.is_empty() {
+            self.push_line_part(tail);
+        }
+    }
+
+    fn push_line_part(&mut self, text: &'a str) {
+        self.edited |= text.contains(EDIT_CHARS);
+        self.line
+            .push(LinePart::Text(self.effect.clone(), self.link.clone(), text));
+    }
+
+    fn push_csi(&mut self, csi: Csi) {
+        self.edited = true;
+        self.line.push(LinePart::Csi(csi, self.effect.clone()));
+    }
+
+    /// Yield the embedded image or file after everything that came before it.
+    fn push_embed(&mut self, embed: Embed) {
+        if !self.line.is_empty() {
+            self.finish_line();
+        }
+        self.ready.push_back(Segment {
+            image: Some(Rc::new(embed)),
+            ..Segment::new(self.effect.clone(), "")
+        });
+    }
+
+    fn finish_line(&mut self) {
+        let parts = mem::take(&mut self.line);
+        if !mem::take(&mut self.edited) {
+            self.ready
+                .extend(parts.into_iter().filter_map(|part| match part {
+                    LinePart::Text(effect, link, text) => Some(Segment {
+                        link,
+                        ..Segment::new(effect, text)
+                    }),
+                    LinePart::Csi(..) => None,
+                }));
+            return;
+        }
+
+        let mut line = Line::default();
+        let mut column: usize = 0;
+        let mut newline = false;
+        for part in parts {
+            let (effect, link, text) = match part {
+                LinePart::Text(effect, link, text) => (effect, link, text),
+                LinePart::Csi(csi, effect) => {
+                    match csi {
+                        Csi::CursorForward(n) => {
+                            column = screen::clamp_cursor(
+                                column,
+                                column.saturating_add(n),
+                                Screen::MAX_COLUMNS,
+                            )
+                        }
+                        Csi::CursorBack(n) => column = column.saturating_sub(n),
+                        Csi::CursorColumn(n) => {
+                            column = screen::clamp_cursor(
+                                column,
+                                n.saturating_sub(1),
+                                Screen::MAX_COLUMNS,
+                            )
+                        }
+                        Csi::EraseInLine(mode) | Csi::EraseInDisplay(mode) => {
+                            line.erase(mode, column, &effect)
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+            };
+
+            for c in text.chars() {
+                match c {
+                    '\r' => column = 0,
+                    '\u{0008}' => column = column.saturating_sub(1),
+                    '\t' => column = screen::next_tab_stop(column),
+                    '\n' => newline = true,
+                    ch => {
+                        line.write(
+                            column,
+                            Cell {
+                                ch,
+                                effect: effect.clone(),
+                                link: link.clone(),
+                            },
+                        );
+                        column += 1;
+                    }
+                }
+            }
+        }
+
+        let mut segments = Vec::new();
+        if newline {
+            line.push_segments_newline(&mut segments);
+        } else {
+            segments = line.segments();
+        }
+        self.ready.extend(segments);
+    }
+}
+impl<'a> Iterator for EditedSegmentIter<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(segment) = self.ready.pop_front() {
+                return Some(segment);
+            }
+
+            match self.markers.next() {
+                Some(Marker::Text(text)) => self.push_text(text),
+                Some(Marker::Sequence(Escape::Csi(Csi::Sgr(sgrs)))) => {
+                    self.effect.apply_sgrs(sgrs);
+                }
+                Some(Marker::Sequence(Escape::Csi(
+                    csi @ (Csi::CursorForward(_)
+                    | Csi::CursorBack(_)
+                    | Csi::CursorColumn(_)
+                    | Csi::EraseInLine(_)
+                    | Csi::EraseInDisplay(_)),
+                ))) => self.push_csi(csi),
+                Some(Marker::Sequence(Escape::Osc(Osc::Hyperlink(link)))) => {
+                    self.link = link.map(Rc::new);
+                }
+                Some(Marker::Sequence(Escape::Osc(Osc::File(file)))) => {
+                    self.push_embed(Embed::File(file));
+                }
+                Some(Marker::Sequence(Escape::Dcs(dcs))) => {
+                    if let Some(image) = SixelImage::from_dcs(&dcs) {
+                        self.push_embed(Embed::Sixel(image));
+                    }
+                }
+                Some(Marker::Sequence(_)) => {}
+                None if self.line.is_empty() => return None,
+                None => self.finish_line(),
+            }
+        }
+    }
+}
+
+/// Create an iterator which iterates over SGR segments with line editing applied.
+///
+/// This works just like [`get_sgr_segments`](crate::get_sgr_segments), but a carriage return moves
+/// back to the start of the line so that the following text overwrites it.
+/// Backspace moves back a single character.
+/// Each overwritten character keeps the [`SgrEffect`] of its final write.
+///
+/// Horizontal cursor movement and erase sequences ([`Csi::EraseInLine`] and [`Csi::EraseInDisplay`])
+/// are applied to the current line.
+/// Since lines are yielded as soon as they're complete, sequences never affect other lines.
+/// Use a [`Screen`](crate::Screen) if that's required.
+///
+/// Sixel graphics and files sent using the iTerm2 protocol are yielded as segments
+/// with the [`image`](Segment::image) set.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let mut segments = yew_ansi::get_edited_segments("[==  ] 50%\r[====] 100%\r\ndone");
+/// assert_eq!(
+///     segments.next(),
+///     Some(Segment::new(SgrEffect::default(), "[====] 100%\n"))
+/// );
+/// assert_eq!(segments.next(), Some(Segment::new(SgrEffect::default(), "done")));
+/// assert_eq!(segments.next(), None);
+/// ```
+pub fn get_edited_segments(s: &str) -> EditedSegmentIter<'_> {
+    EditedSegmentIter::new(s, ParseOptions::default())
+}
+
+/// Same as [`get_edited_segments`] but with the given [`ParseOptions`].
+pub fn get_edited_segments_with_options(s: &str, options: ParseOptions) -> EditedSegmentIter<'_> {
+    EditedSegmentIter::new(s, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphic_rendition::{ColorEffect, ColorName};
+    use std::borrow::Cow;
+
+    fn render(s: &str) -> String {
+        get_edited_segments(s).map(|segment| segment.text).collect()
+    }
+
+    #[test]
+    fn carriage_return() {
+        assert_eq!(render("abc\rX"), "Xbc");
+        assert_eq!(render("abc\r\ndef"), "abc\ndef");
+        assert_eq!(render("a\nbc\rX\nd"), "a\nXc\nd");
+        assert_eq!(render("abc\u{0008}\u{0008}X"), "aXc");
+        assert_eq!(render("50%\r100%\r"), "100%");
+    }
+
+    #[test]
+    fn tab_stops() {
+        assert_eq!(render("a\tb\rX"), "X       b");
+        // the same as on a screen
+        for input in [
+            "a\tb\rX",
+            "ab\tc\u{001b}[3DX",
+            "\tx\u{001b}[1K",
+            "a\tb\tc\u{0008}X",
+        ] {
+            let mut screen = Screen::new();
+            screen.feed(input);
+            let expected = screen
+                .segments()
+                .into_iter()
+                .map(|segment| segment.text)
+                .collect::<String>();
+            assert_eq!(render(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn cursor_movement_is_limited() {
+        let text = render("\u{001b}[999999999Cx\u{001b}[18446744073709551615Cy");
+        assert_eq!(text.len(), Screen::MAX_COLUMNS + 1);
+        assert!(text.ends_with("xy"));
+        assert_eq!(render("\u{001b}[999999999Gx").len(), Screen::MAX_COLUMNS);
+    }
+
+    #[test]
+    fn unedited_lines_are_borrowed() {
+        let segments = get_edited_segments("abc\ndef\nghi").collect::<Vec<_>>();
+        assert_eq!(segments.len(), 2);
+        assert!(segments
+            .iter()
+            .all(|segment| matches!(segment.text, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn overwrite_keeps_last_effect() {
+        let red = SgrEffect {
+            fg: ColorEffect::Name(ColorName::Red),
+            ..Default::default()
+        };
+        let segments = get_edited_segments("abc\r\u{001b}[31mX\u{001b}[0m\n").collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::new(red, "X"),
+                Segment::new(SgrEffect::default(), "bc\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn erase() {
+        assert_eq!(render("abcdef\u{001b}[3D\u{001b}[K\nghi"), "abc\nghi");
+        assert_eq!(render("abc\u{001b}[2Jdef"), "   def");
+
+        let red = SgrEffect {
+            bg: ColorEffect::Name(ColorName::Red),
+            ..Default::default()
+        };
+        let segments = get_edited_segments("ab\u{001b}[41m\u{001b}[K\n").collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::new(SgrEffect::default(), "ab"),
+                Segment {
+                    fill: true,
+                    ..Segment::new(red, "\n")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sixel_image() {
+        let segments = get_edited_segments("ab\rX\u{001b}Pq~\u{001b}\\\ncd").collect::<Vec<_>>();
+        let image = Rc::new(Embed::Sixel(SixelImage::decode("~")));
+        assert_eq!(
+            segments,
+            vec![
+                Segment::new(SgrEffect::default(), "Xb"),
+                Segment {
+                    image: Some(image),
+                    ..Segment::new(SgrEffect::default(), "")
+                },
+                Segment::new(SgrEffect::default(), "\n"),
+                Segment::new(SgrEffect::default(), "cd"),
+            ]
+        );
+    }
+
+    #[test]
+    fn hyperlink() {
+        let link = Rc::new(Hyperlink {
+            id: None,
+            uri: "https://example.com".to_owned(),
+        });
+        let segments = get_edited_segments(
+            "see \u{001b}]8;;https://example.com\u{0007}here\u{001b}]8;;\u{0007}!",
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::new(SgrEffect::default(), "see "),
+                Segment {
+                    link: Some(link),
+                    ..Segment::new(SgrEffect::default(), "here")
+                },
+                Segment::new(SgrEffect::default(), "!"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{graphic_rendition::SgrEffect, SgrSegmentIter};
+
+/// Single line of text containing ANSI escape codes.
+///
+/// The segments borrow from the input, so the line can only be serialized.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AnsiLine<'a> {
+    /// Effect which is active at the start of the line.
+    pub start_effect: SgrEffect,
+    /// Text of the line along with the effect it's displayed with.
+    /// The line ending isn't included.
+    pub segments: Vec<(SgrEffect, &'a str)>,
+}
+
+/// Iterator over the lines in a string slice.
+///
+/// Each item is an [`AnsiLine`].
+///
+/// Returned by [`get_ansi_lines`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct AnsiLineIter<'a> {
+    segments: SgrSegmentIter<'a>,
+    /// Rest of a segment which continues on the next line.
+    rest: Option<(SgrEffect, &'a str)>,
+    /// Effect at the start of the next line.
+    effect: SgrEffect,
+}
+impl<'a> AnsiLineIter<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            segments: crate::get_sgr_segments(s),
+            rest: None,
+            effect: SgrEffect::default(),
+        }
+    }
+}
+impl<'a> Iterator for AnsiLineIter<'a> {
+    type Item = AnsiLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = AnsiLine {
+            start_effect: self.effect.clone(),
+            segments: Vec::new(),
+        };
+        loop {
+            let (effect, text) = match self.rest.take().or_else(|| self.segments.next()) {
+                Some(segment) => segment,
+                // a line without any text at the end doesn't count
+                None if line.segments.is_empty() => return None,
+                None => return Some(line),
+            };
+
+            match text.split_once('\n') {
+                Some((head, tail)) => {
+                    let head = head.strip_suffix('\r').unwrap_or(head);
+                    if !head.is_empty() {
+                        line.segments.push((effect.clone(), head));
+                    }
+                    if !tail.is_empty() {
+                        self.rest = Some((effect.clone(), tail));
+                    }
+                    self.effect = effect;
+                    return Some(line);
+                }
+                None => line.segments.push((effect, text)),
+            }
+        }
+    }
+}
+
+/// Create an iterator over the lines in a string slice.
+///
+/// Lines are split just like [`str::lines`] does it, but the escape sequences are applied.
+/// Each line records the [`SgrEffect`] it starts with, so lines can be displayed on their own.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let red = SgrEffect {
+///     fg: ColorEffect::Name(ColorName::Red),
+///     ..Default::default()
+/// };
+/// let lines = yew_ansi::get_ansi_lines("a\u{001b}[31mb\nc\u{001b}[0m\n").collect::<Vec<_>>();
+/// assert_eq!(
+///     lines,
+///     vec![
+///         AnsiLine {
+///             start_effect: SgrEffect::default(),
+///             segments: vec![(SgrEffect::default(), "a"), (red.clone(), "b")],
+///         },
+///         AnsiLine {
+///             start_effect: red.clone(),
+///             segments: vec![(red, "c")],
+///         },
+///     ]
+/// );
+/// ```
+pub fn get_ansi_lines(s: &str) -> AnsiLineIter<'_> {
+    AnsiLineIter::new(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(s: &str) -> Vec<String> {
+        get_ansi_lines(s)
+            .map(|line| line.segments.iter().map(|(_, text)| *text).collect())
+            .collect()
+    }
+
+    #[test]
+    fn splitting() {
+        for input in ["", "a", "a\n", "a\n\nb", "\n", "a\r\nb\r\n", "\na\nb\n\n"] {
+            assert_eq!(
+                texts(input),
+                input.lines().collect::<Vec<_>>(),
+                "{:?}",
+                input
+            );
+        }
+        assert_eq!(
+            texts("a\u{001b}[1m\nb\u{001b}[0mc\n\u{001b}[0m"),
+            vec!["a", "bc"]
+        );
+    }
+
+    #[test]
+    fn start_effect() {
+        let bold = SgrEffect {
+            bold: true,
+            ..Default::default()
+        };
+        let lines = get_ansi_lines("\u{001b}[1ma\n\nb\u{001b}[0m\nc").collect::<Vec<_>>();
+        let start_effects = lines
+            .iter()
+            .map(|line| line.start_effect.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            start_effects,
+            vec![
+                SgrEffect::default(),
+                bold.clone(),
+                bold,
+                SgrEffect::default()
+            ]
+        );
+        assert_eq!(lines[1].segments, vec![]);
+    }
+}
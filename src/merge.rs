@@ -0,0 +1,114 @@
+use crate::screen::Segment;
+use std::iter::Peekable;
+
+/// Iterator which joins consecutive segments that look the same.
+///
+/// Returned by [`merge_segments`] and [`EditedSegmentIter::merged`](crate::EditedSegmentIter::merged).
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct MergedSegmentIter<'a, I: Iterator<Item = Segment<'a>>> {
+    segments: Peekable<I>,
+}
+impl<'a, I: Iterator<Item = Segment<'a>>> MergedSegmentIter<'a, I> {
+    fn new(segments: I) -> Self {
+        Self {
+            segments: segments.peekable(),
+        }
+    }
+}
+impl<'a, I: Iterator<Item = Segment<'a>>> Iterator for MergedSegmentIter<'a, I> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut segment = self.segments.next()?;
+        if segment.fill || segment.image.is_some() {
+            return Some(segment);
+        }
+
+        while let Some(next) = self.segments.next_if(|next| {
+            !next.fill
+                && next.image.is_none()
+                && next.effect == segment.effect
+                && next.link == segment.link
+        }) {
+            segment.text.to_mut().push_str(&next.text);
+        }
+        Some(segment)
+    }
+}
+
+/// Join consecutive segments with the same [`SgrEffect`](crate::SgrEffect) and hyperlink into a single segment.
+///
+/// Redundant sequences like `ESC[1m` in the middle of bold text split the text into multiple segments,
+/// this puts them back together.
+/// Segments which [fill](Segment::fill) the line or [embed](Segment::image) something are never joined.
+/// The text of a joined segment is owned, everything else is passed through unchanged.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let segments = yew_ansi::merge_segments(yew_ansi::get_edited_segments(
+///     "\u{001b}[1mone\u{001b}[1m two\u{001b}[0m\u{001b}[0m three",
+/// ))
+/// .collect::<Vec<_>>();
+/// let bold = SgrEffect {
+///     bold: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     segments,
+///     vec![
+///         Segment::new(bold, "one two"),
+///         Segment::new(SgrEffect::default(), " three"),
+///     ]
+/// );
+/// ```
+pub fn merge_segments<'a, I>(segments: I) -> MergedSegmentIter<'a, I::IntoIter>
+where
+    I: IntoIterator<Item = Segment<'a>>,
+{
+    MergedSegmentIter::new(segments.into_iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graphic_rendition::SgrEffect, osc::Hyperlink};
+    use std::{borrow::Cow, rc::Rc};
+
+    #[test]
+    fn merging() {
+        let link = Rc::new(Hyperlink {
+            id: None,
+            uri: "https://example.com".to_owned(),
+        });
+        let linked = |text| Segment {
+            link: Some(link.clone()),
+            ..Segment::new(SgrEffect::default(), text)
+        };
+        let filled = Segment {
+            fill: true,
+            ..Segment::new(SgrEffect::default(), "")
+        };
+        let segments = vec![
+            Segment::new(SgrEffect::default(), "a"),
+            Segment::new(SgrEffect::default(), "b"),
+            linked("c"),
+            linked("d"),
+            filled.clone(),
+            Segment::new(SgrEffect::default(), "e"),
+        ];
+        assert_eq!(
+            merge_segments(segments).collect::<Vec<_>>(),
+            vec![
+                Segment::new(SgrEffect::default(), "ab"),
+                linked("cd"),
+                filled,
+                Segment::new(SgrEffect::default(), "e"),
+            ]
+        );
+
+        // a single segment stays borrowed
+        let mut merged = merge_segments(vec![Segment::new(SgrEffect::default(), "a")]);
+        assert!(matches!(merged.next().unwrap().text, Cow::Borrowed("a")));
+    }
+}
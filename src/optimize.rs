@@ -0,0 +1,156 @@
+use crate::{
+    graphic_rendition::{CompactSgrParams, SgrEffect},
+    sequences::{self, Csi, Escape, Marker, ParseOptions, UnknownSequence},
+};
+use std::{borrow::Cow, fmt::Write};
+
+/// Write the SGR sequence which changes the `written` effect into `effect`, if they differ.
+fn write_transition(s: &mut String, written: &mut SgrEffect, effect: &SgrEffect) {
+    let sgrs = written.compact_transition(effect);
+    if !sgrs.is_empty() {
+        let _ = write!(s, "\u{001b}[{}m", CompactSgrParams(&sgrs));
+        *written = effect.clone();
+    }
+}
+
+/// Re-encode the SGR sequences in the string to make them shorter.
+///
+/// SGR sequences are only written right before the text they apply to,
+/// so sequences which don't change anything or are overridden before any text are dropped.
+/// Each change is encoded like [`SgrEffect::transition`] does it,
+/// which picks partial resets like `22` or `39` over a full reset where that's shorter.
+/// The full reset is written as an empty parameter (`ESC[m`).
+///
+/// The text looks exactly the same afterwards and the effect at the end of the string is kept,
+/// so the result can still be concatenated with other text.
+/// All other escape sequences are kept unchanged,
+/// as is a SGR sequence which cuts short an unterminated sequence in front of it.
+/// Only allocates if the string contains any escape sequences.
+///
+/// ```
+/// # use yew_ansi::*;
+/// assert_eq!(
+///     yew_ansi::optimize("\u{001b}[0m\u{001b}[1m\u{001b}[1mbold\u{001b}[0m\u{001b}[1m\u{001b}[31m bold red\u{001b}[0m"),
+///     "\u{001b}[1mbold\u{001b}[31m bold red\u{001b}[m"
+/// );
+/// ```
+pub fn optimize(s: &str) -> Cow<'_, str> {
+    optimize_with_options(s, ParseOptions::default())
+}
+
+/// Same as [`optimize`] but with the given [`ParseOptions`].
+pub fn optimize_with_options(s: &str, options: ParseOptions) -> Cow<'_, str> {
+    if !s.contains(|c| options.is_introducer(c)) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut optimized = String::with_capacity(s.len());
+    let mut written = SgrEffect::default();
+    let mut effect = SgrEffect::default();
+    // whether the previous sequence was cut short by this one, which must then be kept as is
+    let mut keep_next = false;
+    for (marker, span) in sequences::get_markers_with_options(s, options).with_spans() {
+        match marker {
+            Marker::Sequence(Escape::Csi(Csi::Sgr(sgrs))) => {
+                effect.apply_sgrs(sgrs);
+                if !keep_next {
+                    continue;
+                }
+                written = effect.clone();
+            }
+            // dropping the next sequence would join whatever follows it to this one
+            Marker::Sequence(Escape::Unknown(UnknownSequence {
+                final_byte: None, ..
+            })) => {
+                write_transition(&mut optimized, &mut written, &effect);
+                optimized.push_str(&s[span]);
+                keep_next = true;
+                continue;
+            }
+            // erasing uses the current background colour
+            Marker::Text(_)
+            | Marker::Sequence(Escape::Csi(Csi::EraseInLine(_) | Csi::EraseInDisplay(_))) => {
+                write_transition(&mut optimized, &mut written, &effect);
+            }
+            Marker::Sequence(_) => {}
+        }
+        keep_next = false;
+        optimized.push_str(&s[span]);
+    }
+    write_transition(&mut optimized, &mut written, &effect);
+
+    Cow::Owned(optimized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the effect of every character.
+    fn styled_chars(s: &str) -> Vec<(SgrEffect, char)> {
+        crate::get_sgr_segments(s)
+            .flat_map(|(effect, text)| text.chars().map(move |c| (effect.clone(), c)))
+            .collect()
+    }
+
+    #[test]
+    fn optimizing() {
+        assert!(matches!(optimize("plain"), Cow::Borrowed("plain")));
+        assert_eq!(optimize("\u{001b}[0mplain\u{001b}[0m"), "plain");
+        assert_eq!(optimize("a\u{001b}[1m"), "a\u{001b}[1m");
+        assert_eq!(optimize("a\u{001b}[1m\u{001b}[0m"), "a");
+        assert_eq!(
+            optimize("\u{001b}[1;31mab\u{001b}[0m\u{001b}[31mcd"),
+            "\u{001b}[1;31mab\u{001b}[22mcd"
+        );
+        // other sequences are kept
+        assert_eq!(
+            optimize("\u{001b}[1m\u{001b}]0;title\u{0007}\u{001b}[1ma\u{001b}[2J"),
+            "\u{001b}]0;title\u{0007}\u{001b}[1ma\u{001b}[2J"
+        );
+        assert_eq!(
+            optimize("\u{001b}[41m\u{001b}[K\u{001b}[0m"),
+            "\u{001b}[41m\u{001b}[K\u{001b}[m"
+        );
+    }
+
+    #[test]
+    fn compact_encoding() {
+        // the reset is an empty parameter
+        assert_eq!(
+            optimize("\u{001b}[1;31mab\u{001b}[0;32mcd"),
+            "\u{001b}[1;31mab\u{001b}[;32mcd"
+        );
+        // RGB colours are kept, terminals don't agree on the colours of the palette
+        assert_eq!(optimize("\u{001b}[38;2;0;0;0ma"), "\u{001b}[38;2;0;0;0ma");
+    }
+
+    #[test]
+    fn unterminated_sequences() {
+        for input in [
+            "\u{001b}\u{001b}[mFoo",
+            "\u{001b}[1m\u{001b}\u{001b}[1mFoo",
+            "\u{001b}(\u{001b}[0mFoo",
+            "\u{001b}[1m\u{001b}\u{001b}[0m\u{001b}[1mFoo",
+        ] {
+            let optimized = optimize(input);
+            assert_eq!(optimized, input);
+            assert_eq!(styled_chars(&optimized), styled_chars(input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn looks_the_same() {
+        let inputs = [
+            "\u{001b}[1m\u{001b}[1mbold\u{001b}[0m\u{001b}[0m plain",
+            "\u{001b}[38;2;1;2;3;48;5;200mrgb\u{001b}[39mbg\u{001b}[49m\u{001b}[4:3mcurly\u{001b}[24m",
+            "\u{001b}[2mfaint\u{001b}[1mfaint bold\u{001b}[22mnone\u{001b}[7;8;9mx\u{001b}[27;28;29m",
+            "\u{001b}[31m\u{001b}[32m\u{001b}[33mno\u{001b}[30;40;1;3;4;5;9;53mtext\u{001b}[0m\u{001b}[91m!",
+        ];
+        for input in inputs {
+            let optimized = optimize(input);
+            assert_eq!(styled_chars(&optimized), styled_chars(input), "{:?}", input);
+            assert!(optimized.len() <= input.len(), "{:?}", optimized);
+        }
+    }
+}
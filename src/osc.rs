@@ -0,0 +1,480 @@
+use crate::{control_string, cursor::CharCursor, sequences::ParseOptions};
+use std::fmt::{self, Display, Formatter};
+
+/// Target of a hyperlink.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hyperlink {
+    /// Identifier used to connect links which aren't next to each other.
+    pub id: Option<String>,
+    pub uri: String,
+}
+impl Hyperlink {
+    /// Parse the hyperlink from the `key=value` parameters (separated by ':') and the URI.
+    fn from_parts(params: &str, uri: &str) -> Self {
+        let id = params
+            .split(':')
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| *key == "id")
+            .map(|(_, value)| value.to_owned());
+        Self {
+            id,
+            uri: uri.to_owned(),
+        }
+    }
+
+    /// Get the scheme of the URI.
+    /// Returns `None` if the URI doesn't start with a valid scheme.
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let link = Hyperlink { id: None, uri: "https://example.com".to_owned() };
+    /// assert_eq!(link.scheme(), Some("https"));
+    /// ```
+    pub fn scheme(&self) -> Option<&str> {
+        let (scheme, _) = self.uri.split_once(':')?;
+        let mut chars = scheme.chars();
+        let valid = chars.next()?.is_ascii_alphabetic()
+            && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if valid {
+            Some(scheme)
+        } else {
+            None
+        }
+    }
+}
+
+/// Desktop notification.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Notification {
+    pub title: Option<String>,
+    pub body: String,
+}
+
+/// Size hint of an [`EmbeddedFile`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageDimension {
+    /// Use the size of the image.
+    #[default]
+    Auto,
+    /// Number of character cells.
+    Cells(usize),
+    Pixels(usize),
+    /// Percentage of the available space.
+    Percent(usize),
+}
+impl ImageDimension {
+    /// Parse the dimension, which is either `auto`, `N`, `Npx`, or `N%`.
+    fn parse(s: &str) -> Option<Self> {
+        if s == "auto" {
+            Some(Self::Auto)
+        } else if let Some(n) = s.strip_suffix("px") {
+            n.parse().ok().map(Self::Pixels)
+        } else if let Some(n) = s.strip_suffix('%') {
+            n.parse().ok().map(Self::Percent)
+        } else {
+            s.parse().ok().map(Self::Cells)
+        }
+    }
+}
+impl Display for ImageDimension {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Auto => f.write_str("auto"),
+            Self::Cells(n) => write!(f, "{}", n),
+            Self::Pixels(n) => write!(f, "{}px", n),
+            Self::Percent(n) => write!(f, "{}%", n),
+        }
+    }
+}
+
+/// File sent using the iTerm2 inline images protocol (`OSC 1337 ; File=`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmbeddedFile {
+    /// Name of the file.
+    pub name: Option<String>,
+    /// Size of the file in bytes, as reported by the sender.
+    pub size: Option<usize>,
+    pub width: ImageDimension,
+    pub height: ImageDimension,
+    /// Whether the image keeps its aspect ratio if both dimensions are given.
+    pub preserve_aspect_ratio: bool,
+    /// Whether the file should be displayed instead of downloaded.
+    pub inline: bool,
+    /// Base64 encoded contents.
+    pub data: String,
+}
+impl EmbeddedFile {
+    /// Parse the `key=value` arguments (separated by ';') and the contents following the `:`.
+    fn from_args(args: &str) -> Option<Self> {
+        let (args, data) = args.split_once(':')?;
+        let mut file = Self {
+            name: None,
+            size: None,
+            width: ImageDimension::Auto,
+            height: ImageDimension::Auto,
+            preserve_aspect_ratio: true,
+            inline: false,
+            data: data.to_owned(),
+        };
+        for (key, value) in args.split(';').filter_map(|arg| arg.split_once('=')) {
+            match key {
+                "name" => {
+                    file.name =
+                        decode_base64(value).map(|name| String::from_utf8_lossy(&name).into_owned())
+                }
+                "size" => file.size = value.parse().ok(),
+                "width" => file.width = ImageDimension::parse(value).unwrap_or_default(),
+                "height" => file.height = ImageDimension::parse(value).unwrap_or_default(),
+                "preserveAspectRatio" => file.preserve_aspect_ratio = value != "0",
+                "inline" => file.inline = value == "1",
+                _ => {}
+            }
+        }
+        Some(file)
+    }
+
+    /// Decode the contents.
+    /// Returns `None` if they aren't valid base64.
+    pub fn contents(&self) -> Option<Vec<u8>> {
+        decode_base64(&self.data)
+    }
+
+    /// Get the MIME type of the contents if they're a PNG, JPEG, or GIF image.
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let markers = yew_ansi::get_markers("\u{001b}]1337;File=inline=1:R0lGODlhAQABAAAAACw=\u{0007}").collect::<Vec<_>>();
+    /// match &markers[0] {
+    ///     Marker::Sequence(Escape::Osc(Osc::File(file))) => assert_eq!(file.image_type(), Some("image/gif")),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn image_type(&self) -> Option<&'static str> {
+        // only the start is needed to check the signature
+        let end = self
+            .data
+            .char_indices()
+            .nth(12)
+            .map_or(self.data.len(), |(i, _)| i);
+        let header = decode_base64(&self.data[..end])?;
+        if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some("image/png")
+        } else if header.starts_with(b"\xff\xd8\xff") {
+            Some("image/jpeg")
+        } else if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+            Some("image/gif")
+        } else {
+            None
+        }
+    }
+}
+
+/// Encode bytes as standard base64 with padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let buf = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |buf, (i, &b)| buf | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(char::from(ALPHABET[(buf >> (18 - 6 * i)) as usize & 0x3f]));
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// Decode standard base64, ignoring padding and whitespace.
+/// Returns `None` if the input contains any other characters.
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let mut buf: u32 = 0;
+    let mut bits = 0;
+    for c in s.chars() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            '=' => continue,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        buf = (buf << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Operating System Command.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Osc {
+    /// Set the icon name and window title (OSC 0).
+    SetIconNameAndTitle(String),
+    /// Set the icon name (OSC 1).
+    SetIconName(String),
+    /// Set the window title (OSC 2).
+    SetTitle(String),
+    /// Report the current working directory as a `file://` URL (OSC 7).
+    WorkingDirectory(String),
+    /// Start a hyperlink (OSC 8).
+    /// `None` ends the current hyperlink.
+    Hyperlink(Option<Hyperlink>),
+    /// Show a desktop notification (OSC 9 and OSC 777).
+    ///
+    /// OSC 9 notifications never have a title.
+    Notification(Notification),
+    /// Transfer a file using the iTerm2 protocol (OSC 1337 File).
+    File(EmbeddedFile),
+}
+impl Osc {
+    pub(crate) const START: char = ']';
+
+    /// Read everything up to the string terminator.
+    /// The terminator is either BEL or ST (`ESC \`, or U+009C if C1 controls are enabled).
+    /// Returns `None` if the command isn't terminated.
+    pub(crate) fn read_payload<'a>(
+        cursor: &mut CharCursor<'a>,
+        options: ParseOptions,
+    ) -> Option<&'a str> {
+        control_string::read_payload(cursor, options, true, false)
+    }
+
+    /// Parse the command from the payload.
+    /// Returns `None` if the command isn't supported.
+    pub(crate) fn from_payload(payload: &str) -> Option<Self> {
+        let (command, args) = payload.split_once(';').unwrap_or((payload, ""));
+        match command {
+            "0" => Some(Self::SetIconNameAndTitle(args.to_owned())),
+            "1" => Some(Self::SetIconName(args.to_owned())),
+            "2" => Some(Self::SetTitle(args.to_owned())),
+            "7" => Some(Self::WorkingDirectory(args.to_owned())),
+            "8" => {
+                let (params, uri) = args.split_once(';')?;
+                let link = if uri.is_empty() {
+                    None
+                } else {
+                    Some(Hyperlink::from_parts(params, uri))
+                };
+                Some(Self::Hyperlink(link))
+            }
+            "9" => {
+                // ConEmu uses OSC 9 with a numeric sub-command for other purposes (like progress reports).
+                let is_subcommand =
+                    matches!(args.split_once(';'), Some((n, _)) if n.parse::<u8>().is_ok());
+                if is_subcommand {
+                    None
+                } else {
+                    Some(Self::Notification(Notification {
+                        title: None,
+                        body: args.to_owned(),
+                    }))
+                }
+            }
+            "777" => {
+                let mut parts = args.splitn(3, ';');
+                if parts.next()? != "notify" {
+                    return None;
+                }
+                let title = parts.next()?.to_owned();
+                let body = parts.next().unwrap_or_default().to_owned();
+                Some(Self::Notification(Notification {
+                    title: Some(title),
+                    body,
+                }))
+            }
+            "1337" => {
+                let args = args.strip_prefix("File=")?;
+                EmbeddedFile::from_args(args).map(Self::File)
+            }
+            _ => None,
+        }
+    }
+}
+/// Writes the canonical form of the command, terminated with ST (`ESC \\`).
+impl Display for Osc {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("\u{001b}]")?;
+        match self {
+            Self::SetIconNameAndTitle(title) => write!(f, "0;{}", title)?,
+            Self::SetIconName(name) => write!(f, "1;{}", name)?,
+            Self::SetTitle(title) => write!(f, "2;{}", title)?,
+            Self::WorkingDirectory(url) => write!(f, "7;{}", url)?,
+            Self::Hyperlink(Some(Hyperlink { id: Some(id), uri })) => {
+                write!(f, "8;id={};{}", id, uri)?
+            }
+            Self::Hyperlink(Some(Hyperlink { id: None, uri })) => write!(f, "8;;{}", uri)?,
+            Self::Hyperlink(None) => f.write_str("8;;")?,
+            Self::Notification(Notification { title: None, body }) => write!(f, "9;{}", body)?,
+            Self::Notification(Notification {
+                title: Some(title),
+                body,
+            }) => write!(f, "777;notify;{};{}", title, body)?,
+            Self::File(file) => {
+                f.write_str("1337;File=")?;
+                let mut args = Vec::new();
+                if let Some(name) = &file.name {
+                    args.push(format!("name={}", encode_base64(name.as_bytes())));
+                }
+                if let Some(size) = file.size {
+                    args.push(format!("size={}", size));
+                }
+                if file.width != ImageDimension::Auto {
+                    args.push(format!("width={}", file.width));
+                }
+                if file.height != ImageDimension::Auto {
+                    args.push(format!("height={}", file.height));
+                }
+                if !file.preserve_aspect_ratio {
+                    args.push("preserveAspectRatio=0".to_owned());
+                }
+                if file.inline {
+                    args.push("inline=1".to_owned());
+                }
+                write!(f, "{}:{}", args.join(";"), file.data)?;
+            }
+        }
+        f.write_str("\u{001b}\\")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Option<Osc> {
+        let s = s.replace("ST", "\u{001b}\\");
+        let mut cursor = CharCursor::new(&s);
+        cursor.read_char(Osc::START)?;
+        Osc::read_payload(&mut cursor, ParseOptions::default()).and_then(Osc::from_payload)
+    }
+
+    #[test]
+    fn parsing_hyperlink() {
+        assert_eq!(
+            parse("]8;;https://example.comST"),
+            Some(Osc::Hyperlink(Some(Hyperlink {
+                id: None,
+                uri: "https://example.com".to_owned()
+            })))
+        );
+        assert_eq!(
+            parse("]8;id=a:foo=bar;file:///tmp\u{0007}"),
+            Some(Osc::Hyperlink(Some(Hyperlink {
+                id: Some("a".to_owned()),
+                uri: "file:///tmp".to_owned()
+            })))
+        );
+        assert_eq!(parse("]8;;ST"), Some(Osc::Hyperlink(None)));
+        assert_eq!(parse("]8;;https://example.com"), None);
+        assert_eq!(parse("]8;;https://example.com\u{001b}[0m"), None);
+    }
+
+    #[test]
+    fn parsing_title() {
+        assert_eq!(
+            parse("]0;cargo build\u{0007}"),
+            Some(Osc::SetIconNameAndTitle("cargo build".to_owned()))
+        );
+        assert_eq!(parse("]2;a;b;cST"), Some(Osc::SetTitle("a;b;c".to_owned())));
+        assert_eq!(
+            parse("]7;file://host/home/user\u{0007}"),
+            Some(Osc::WorkingDirectory("file://host/home/user".to_owned()))
+        );
+    }
+
+    #[test]
+    fn parsing_notification() {
+        assert_eq!(
+            parse("]9;Build finished\u{0007}"),
+            Some(Osc::Notification(Notification {
+                title: None,
+                body: "Build finished".to_owned()
+            }))
+        );
+        assert_eq!(parse("]9;4;1;50\u{0007}"), None);
+        assert_eq!(
+            parse("]777;notify;CI;Tests passedST"),
+            Some(Osc::Notification(Notification {
+                title: Some("CI".to_owned()),
+                body: "Tests passed".to_owned()
+            }))
+        );
+        assert_eq!(parse("]777;preexec\u{0007}"), None);
+    }
+
+    #[test]
+    fn parsing_file() {
+        assert_eq!(
+            parse("]1337;File=name=YS5wbmc=;size=3;width=10;height=50%;preserveAspectRatio=0;inline=1:AAEC\u{0007}"),
+            Some(Osc::File(EmbeddedFile {
+                name: Some("a.png".to_owned()),
+                size: Some(3),
+                width: ImageDimension::Cells(10),
+                height: ImageDimension::Percent(50),
+                preserve_aspect_ratio: false,
+                inline: true,
+                data: "AAEC".to_owned(),
+            }))
+        );
+        assert_eq!(
+            parse("]1337;File=width=20px:AAEC\u{0007}"),
+            Some(Osc::File(EmbeddedFile {
+                name: None,
+                size: None,
+                width: ImageDimension::Pixels(20),
+                height: ImageDimension::Auto,
+                preserve_aspect_ratio: true,
+                inline: false,
+                data: "AAEC".to_owned(),
+            }))
+        );
+        // the contents are required
+        assert_eq!(parse("]1337;File=inline=1\u{0007}"), None);
+        assert_eq!(parse("]1337;SetMark\u{0007}"), None);
+    }
+
+    #[test]
+    fn decoding_base64() {
+        assert_eq!(encode_base64(b"hi"), "aGk=");
+        assert_eq!(encode_base64(b"a.png"), "YS5wbmc=");
+        assert_eq!(encode_base64(&[0, 1, 2]), "AAEC");
+        assert_eq!(decode_base64("AAEC"), Some(vec![0, 1, 2]));
+        assert_eq!(decode_base64("aGk="), Some(b"hi".to_vec()));
+        assert_eq!(decode_base64("aG\nk"), Some(b"hi".to_vec()));
+        assert_eq!(decode_base64("a?"), None);
+    }
+
+    #[test]
+    fn scheme() {
+        let scheme = |uri: &str| {
+            Hyperlink {
+                id: None,
+                uri: uri.to_owned(),
+            }
+            .scheme()
+            .map(str::to_owned)
+        };
+        assert_eq!(scheme("mailto:me@example.com").as_deref(), Some("mailto"));
+        assert_eq!(scheme(" javascript:alert(1)"), None);
+        assert_eq!(scheme("java\tscript:alert(1)"), None);
+        assert_eq!(scheme("/relative/path"), None);
+    }
+}
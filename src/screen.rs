@@ -0,0 +1,565 @@
+use crate::{
+    graphic_rendition::{ColorEffect, SgrEffect},
+    osc::{EmbeddedFile, Hyperlink, Osc},
+    sequences::{self, Csi, EraseMode, Escape, Marker, ParseOptions},
+    sixel::SixelImage,
+};
+use std::{borrow::Cow, rc::Rc};
+
+/// A single character on a [`Screen`] along with the effect it was written with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub effect: SgrEffect,
+    /// Hyperlink the character is part of.
+    pub link: Option<Rc<Hyperlink>>,
+}
+impl Cell {
+    fn blank(effect: SgrEffect) -> Self {
+        Self {
+            ch: ' ',
+            effect,
+            link: None,
+        }
+    }
+}
+
+/// Image or file embedded in the text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Embed {
+    Sixel(SixelImage),
+    File(EmbeddedFile),
+}
+
+/// Run of text which shares the same [`SgrEffect`] and hyperlink.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment<'a> {
+    pub effect: SgrEffect,
+    pub text: Cow<'a, str>,
+    /// Hyperlink the text is part of.
+    pub link: Option<Rc<Hyperlink>>,
+    /// Whether the background of this segment extends to the end of the line.
+    ///
+    /// This is the result of erasing part of a line while a background colour is set.
+    /// Such a segment is always the last one of its line and its text is either empty or a newline.
+    pub fill: bool,
+    /// Image or file shown in place of the text, the text of such a segment is empty.
+    ///
+    /// These are only yielded by [`get_edited_segments`](crate::get_edited_segments).
+    pub image: Option<Rc<Embed>>,
+}
+impl<'a> Segment<'a> {
+    /// Create a segment which doesn't fill the line, isn't part of a hyperlink, and doesn't embed anything.
+    pub fn new(effect: SgrEffect, text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            effect,
+            text: text.into(),
+            link: None,
+            fill: false,
+            image: None,
+        }
+    }
+}
+
+/// A single row of [`Cell`]s.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Line {
+    cells: Vec<Cell>,
+    fill: Option<SgrEffect>,
+}
+impl Line {
+    /// Get the cells in this line.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Get the effect which fills the rest of the line after the last cell.
+    pub fn fill(&self) -> Option<&SgrEffect> {
+        self.fill.as_ref()
+    }
+
+    /// Cell for gaps in the line that were never written to.
+    fn gap(&self) -> Cell {
+        Cell::blank(self.fill.clone().unwrap_or_default())
+    }
+
+    /// Write a cell at the given column, overwriting whatever was there.
+    /// If the column is past the end of the line, the gap is filled with blank cells.
+    pub fn write(&mut self, column: usize, cell: Cell) {
+        if column < self.cells.len() {
+            self.cells[column] = cell;
+        } else {
+            self.cells.resize(column, self.gap());
+            self.cells.push(cell);
+        }
+    }
+
+    /// Erase part of the line relative to the given column.
+    ///
+    /// Erased cells keep the background colour of `effect`.
+    /// If the background colour is set, erasing to the end of the line fills the rest of the line with it.
+    pub fn erase(&mut self, mode: EraseMode, column: usize, effect: &SgrEffect) {
+        let blank = Cell::blank(SgrEffect {
+            bg: effect.bg.clone(),
+            ..SgrEffect::default()
+        });
+        let fill = if blank.effect.bg == ColorEffect::None {
+            None
+        } else {
+            Some(blank.effect.clone())
+        };
+
+        match mode {
+            EraseMode::ToEnd => {
+                self.cells.truncate(column);
+                if self.fill.is_some() || fill.is_some() {
+                    self.cells.resize(column, self.gap());
+                }
+                self.fill = fill;
+            }
+            EraseMode::ToStart => {
+                let end = column + 1;
+                if end > self.cells.len() {
+                    self.cells.resize(end, self.gap());
+                }
+                self.cells[..end].fill(blank);
+            }
+            EraseMode::All => {
+                self.cells.clear();
+                self.fill = fill;
+            }
+        }
+    }
+
+    /// Get the runs of consecutive cells which share the same effect and hyperlink.
+    pub fn segments(&self) -> Vec<Segment<'static>> {
+        let mut segments = Vec::new();
+        self.push_segments(&mut segments);
+        segments
+    }
+
+    fn push_segments(&self, segments: &mut Vec<Segment>) {
+        let start = segments.len();
+        for cell in &self.cells {
+            match segments[start..].last_mut() {
+                Some(segment) if segment.effect == cell.effect && segment.link == cell.link => {
+                    segment.text.to_mut().push(cell.ch)
+                }
+                _ => segments.push(Segment {
+                    link: cell.link.clone(),
+                    ..Segment::new(cell.effect.clone(), cell.ch.to_string())
+                }),
+            }
+        }
+
+        if let Some(effect) = &self.fill {
+            segments.push(Segment {
+                fill: true,
+                ..Segment::new(effect.clone(), "")
+            });
+        }
+    }
+
+    /// Push the segments of a line that ends with a newline.
+    ///
+    /// The newline is added to the last segment of the line.
+    pub(crate) fn push_segments_newline(&self, segments: &mut Vec<Segment>) {
+        let start = segments.len();
+        self.push_segments(segments);
+        match segments[start..].last_mut() {
+            Some(segment) => segment.text.to_mut().push('\n'),
+            None => segments.push(Segment::new(SgrEffect::default(), "\n")),
+        }
+    }
+}
+
+/// Get the position the cursor ends up at when it's moved from `current` to `target`.
+///
+/// The cursor can't be moved to `limit` or beyond,
+/// but a cursor which is already there isn't moved back either.
+pub(crate) fn clamp_cursor(current: usize, target: usize, limit: usize) -> usize {
+    if target <= current {
+        target
+    } else {
+        target.min(limit - 1).max(current)
+    }
+}
+
+/// Get the column of the next tab stop after `column`.
+pub(crate) fn next_tab_stop(column: usize) -> usize {
+    (column / Screen::TAB_WIDTH + 1) * Screen::TAB_WIDTH
+}
+
+/// Virtual terminal screen.
+///
+/// Text is written to a grid of [`Line`]s and escape sequences which move the cursor are applied to it.
+/// The screen grows as needed, it has no fixed size.
+/// Rows and columns are 0-based and absolute positions are relative to the first line.
+/// Images and files can't be placed on the screen, so their sequences are ignored
+/// and [`Segment::image`] is always empty.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let mut screen = Screen::new();
+/// screen.feed("Hello World\u{001b}[5DThere");
+/// assert_eq!(
+///     screen.segments(),
+///     vec![Segment::new(SgrEffect::default(), "Hello There")]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Screen {
+    lines: Vec<Line>,
+    row: usize,
+    column: usize,
+    effect: SgrEffect,
+    link: Option<Rc<Hyperlink>>,
+    options: ParseOptions,
+    /// Number of cells in all lines.
+    cells: usize,
+}
+impl Screen {
+    const TAB_WIDTH: usize = 8;
+    /// Number of rows the cursor can be moved to using escape sequences.
+    ///
+    /// Line breaks still add rows beyond this,
+    /// but escape sequences never move the cursor further down than that.
+    pub const MAX_ROWS: usize = 100_000;
+    /// Number of columns the cursor can be moved to using escape sequences.
+    ///
+    /// Text still extends a line beyond this,
+    /// but escape sequences never move the cursor further to the right than that.
+    pub const MAX_COLUMNS: usize = 1024;
+    /// Number of cells the screen can hold before moving the cursor no longer adds blank cells.
+    ///
+    /// Once the screen holds this many cells, text written past the end of a line
+    /// is put right after the last cell instead of leaving a gap.
+    /// Text itself is never dropped, so the screen can still grow beyond this.
+    pub const MAX_CELLS: usize = 1 << 20;
+
+    /// Create an empty screen with the cursor in the top left corner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty screen which parses its input using the given [`ParseOptions`].
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Get the lines of the screen.
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Get the cursor position as a `(row, column)` tuple.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.column)
+    }
+
+    /// Get the effect that is applied to newly written text.
+    pub fn effect(&self) -> &SgrEffect {
+        &self.effect
+    }
+
+    /// Write text containing ANSI escape sequences to the screen.
+    pub fn feed(&mut self, s: &str) {
+        for marker in sequences::get_markers_with_options(s, self.options) {
+            self.apply_marker(&marker);
+        }
+    }
+
+    /// Apply a single [`Marker`] to the screen.
+    pub fn apply_marker(&mut self, marker: &Marker) {
+        match marker {
+            Marker::Text(text) => self.print(text),
+            Marker::Sequence(Escape::Csi(csi)) => self.apply_csi(csi),
+            Marker::Sequence(Escape::Osc(osc)) => self.apply_osc(osc),
+            // string sequences and unknown sequences don't affect the screen
+            Marker::Sequence(_) => {}
+        }
+    }
+
+    /// Apply an operating system command to the screen.
+    pub fn apply_osc(&mut self, osc: &Osc) {
+        // hyperlinks are the only commands which affect the screen
+        if let Osc::Hyperlink(link) = osc {
+            self.link = link.clone().map(Rc::new);
+        }
+    }
+
+    /// Apply a control sequence to the screen.
+    pub fn apply_csi(&mut self, csi: &Csi) {
+        match csi {
+            Csi::Sgr(sgrs) => self.effect.apply_sgrs(sgrs),
+            Csi::CursorUp(n) => self.row = self.row.saturating_sub(*n),
+            Csi::CursorDown(n) => self.move_to_row(self.row.saturating_add(*n)),
+            Csi::CursorForward(n) => self.move_to_column(self.column.saturating_add(*n)),
+            Csi::CursorBack(n) => self.column = self.column.saturating_sub(*n),
+            Csi::CursorNextLine(n) => {
+                self.move_to_row(self.row.saturating_add(*n));
+                self.column = 0;
+            }
+            Csi::CursorPreviousLine(n) => {
+                self.row = self.row.saturating_sub(*n);
+                self.column = 0;
+            }
+            Csi::CursorColumn(column) => self.move_to_column(column.saturating_sub(1)),
+            Csi::CursorPosition { row, column } => {
+                self.move_to_row(row.saturating_sub(1));
+                self.move_to_column(column.saturating_sub(1));
+            }
+            Csi::EraseInDisplay(mode) => self.erase_display(*mode),
+            Csi::EraseInLine(mode) => self.erase_line(*mode),
+            Csi::Unknown(_) => {}
+        }
+    }
+
+    fn move_to_row(&mut self, row: usize) {
+        self.row = clamp_cursor(self.row, row, Self::MAX_ROWS);
+    }
+
+    fn move_to_column(&mut self, column: usize) {
+        self.column = clamp_cursor(self.column, column, Self::MAX_COLUMNS);
+    }
+
+    fn erase_line(&mut self, mode: EraseMode) {
+        let effect = self.effect.clone();
+        let column = self.available_column();
+        self.edit_line(|line| line.erase(mode, column, &effect));
+    }
+
+    fn erase_display(&mut self, mode: EraseMode) {
+        let lines = match mode {
+            EraseMode::ToEnd => (self.row + 1).min(self.lines.len())..self.lines.len(),
+            EraseMode::ToStart => 0..self.row.min(self.lines.len()),
+            EraseMode::All => 0..self.lines.len(),
+        };
+        for line in &mut self.lines[lines] {
+            self.cells -= line.cells.len();
+            line.erase(EraseMode::All, 0, &self.effect);
+        }
+        if mode != EraseMode::All {
+            self.erase_line(mode);
+        }
+    }
+
+    fn current_line(&mut self) -> &mut Line {
+        if self.row >= self.lines.len() {
+            self.lines.resize_with(self.row + 1, Line::default);
+        }
+        &mut self.lines[self.row]
+    }
+
+    /// Change the current line while keeping track of the number of cells.
+    fn edit_line(&mut self, edit: impl FnOnce(&mut Line)) {
+        let line = self.current_line();
+        let before = line.cells.len();
+        edit(line);
+        let after = line.cells.len();
+        self.cells = self.cells + after - before;
+    }
+
+    /// Get the column of the cursor, or the end of the current line
+    /// if filling the gap up to the cursor would exceed [`Screen::MAX_CELLS`].
+    fn available_column(&self) -> usize {
+        let len = self.lines.get(self.row).map_or(0, |line| line.cells.len());
+        if self.column > len && self.cells + (self.column - len) > Self::MAX_CELLS {
+            len
+        } else {
+            self.column
+        }
+    }
+
+    /// Write text without escape sequences at the cursor position.
+    ///
+    /// A newline moves the cursor to the start of the next line
+    /// and a carriage return moves it to the start of the current one.
+    pub fn print(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\r' => self.column = 0,
+                '\n' => {
+                    self.row += 1;
+                    self.column = 0;
+                    // make sure trailing newlines are kept
+                    self.current_line();
+                }
+                '\u{0008}' => self.column = self.column.saturating_sub(1),
+                '\t' => self.column = next_tab_stop(self.column),
+                c => {
+                    let cell = Cell {
+                        ch: c,
+                        effect: self.effect.clone(),
+                        link: self.link.clone(),
+                    };
+                    let column = self.available_column();
+                    self.edit_line(|line| line.write(column, cell));
+                    self.column = column + 1;
+                }
+            }
+        }
+    }
+
+    /// Get the runs of consecutive cells which share the same effect and hyperlink.
+    /// Lines are joined using newlines, segments never span multiple lines.
+    pub fn segments(&self) -> Vec<Segment<'static>> {
+        let mut segments = Vec::new();
+        if let Some((last, lines)) = self.lines.split_last() {
+            for line in lines {
+                line.push_segments_newline(&mut segments);
+            }
+            last.push_segments(&mut segments);
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphic_rendition::{ColorEffect, ColorName};
+
+    fn render(s: &str) -> String {
+        let mut screen = Screen::new();
+        screen.feed(&s.replace("CSI ", "\u{001b}["));
+        screen
+            .segments()
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect()
+    }
+
+    #[test]
+    fn cursor_movement() {
+        assert_eq!(render("abc\ndef\nCSI 2Ax"), "xbc\ndef\n");
+        assert_eq!(render("abc\ndefCSI AX"), "abcX\ndef");
+        assert_eq!(render("abcCSI 2DX"), "aXc");
+        assert_eq!(render("aCSI 2CX"), "a  X");
+        assert_eq!(render("abc\ndefCSI 1;2HX"), "aXc\ndef");
+        assert_eq!(render("abcdefCSI 3GX"), "abXdef");
+        assert_eq!(render("abc\ndefCSI FX"), "Xbc\ndef");
+        assert_eq!(render("abcCSI 2EX"), "abc\n\nX");
+        assert_eq!(render("line\n"), "line\n");
+        assert_eq!(render("50%\r100%\r\ndone"), "100%\ndone");
+    }
+
+    #[test]
+    fn cursor_movement_is_limited() {
+        for input in [
+            "CSI 999999999Cx",
+            "CSI 40000000Bx",
+            "CSI 999999999;999999999Hx",
+            "CSI 999999999Gx",
+            "CSI 18446744073709551615BCSI 18446744073709551615Ex",
+        ] {
+            let mut screen = Screen::new();
+            screen.feed(&input.replace("CSI ", "\u{001b}["));
+            let (row, column) = screen.cursor();
+            assert!(row < Screen::MAX_ROWS, "{:?}", input);
+            assert!(column <= Screen::MAX_COLUMNS, "{:?}", input);
+            assert!(screen.lines().len() <= Screen::MAX_ROWS, "{:?}", input);
+        }
+
+        // text still goes beyond the limit, but the cursor isn't moved back
+        let mut screen = Screen::new();
+        screen.feed(&"x".repeat(Screen::MAX_COLUMNS + 10));
+        screen.feed("\u{001b}[5C");
+        assert_eq!(screen.cursor(), (0, Screen::MAX_COLUMNS + 10));
+    }
+
+    #[test]
+    fn cells_are_limited() {
+        let mut screen = Screen::new();
+        for row in 1..=2000 {
+            screen.feed(&format!("\u{001b}[{};1024Hx", row));
+        }
+        let cells = screen
+            .lines()
+            .iter()
+            .map(|line| line.cells().len())
+            .sum::<usize>();
+        assert!(cells <= Screen::MAX_CELLS + 2000, "{}", cells);
+        assert_eq!(screen.cells, cells);
+        // the text is still there
+        assert_eq!(screen.lines()[1999].cells().last().unwrap().ch, 'x');
+
+        // erasing frees up cells again
+        screen.feed("\u{001b}[2J\u{001b}[1;5Hx");
+        assert_eq!(screen.cells, 5);
+    }
+
+    #[test]
+    fn erase() {
+        assert_eq!(render("abcdefCSI 3DCSI K"), "abc");
+        assert_eq!(render("abcdefCSI 3DCSI 1KX"), "   Xef");
+        assert_eq!(render("abcCSI 2KX"), "   X");
+        assert_eq!(render("abc\ndef\nghiCSI ACSI J"), "abc\ndef\n");
+        assert_eq!(render("abc\ndef\nghiCSI ACSI 1J"), "\n    \nghi");
+        assert_eq!(render("abc\ndefCSI 2JX"), "\n   X");
+        // the cursor is below the last line
+        assert_eq!(render("CSI J"), "");
+        assert_eq!(render("CSI 3BCSI J"), "\n\n\n");
+        assert_eq!(render("abc\nCSI J"), "abc\n");
+    }
+
+    #[test]
+    fn erase_background() {
+        let mut screen = Screen::new();
+        screen.feed("ab\u{001b}[41m\u{001b}[K\u{001b}[0m\ncd");
+        let red = SgrEffect {
+            bg: ColorEffect::Name(ColorName::Red),
+            ..Default::default()
+        };
+        assert_eq!(
+            screen.segments(),
+            vec![
+                Segment::new(SgrEffect::default(), "ab"),
+                Segment {
+                    fill: true,
+                    ..Segment::new(red, "\n")
+                },
+                Segment::new(SgrEffect::default(), "cd"),
+            ]
+        );
+    }
+
+    #[test]
+    fn effects() {
+        let mut screen = Screen::new();
+        screen.feed("ab\u{001b}[2D\u{001b}[31mX");
+        let red = SgrEffect {
+            fg: ColorEffect::Name(ColorName::Red),
+            ..Default::default()
+        };
+        assert_eq!(
+            screen.segments(),
+            vec![
+                Segment::new(red, "X"),
+                Segment::new(SgrEffect::default(), "b"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let segments = crate::get_edited_segments(
+            "\u{001b}]8;;https://example.com\u{001b}\\\u{001b}[1mlink\u{001b}]8;;\u{001b}\\\u{001b}Pq#1;2;100;0;0#1~\u{001b}\\",
+        )
+        .collect::<Vec<_>>();
+        assert!(segments[0].link.is_some());
+        assert!(segments.iter().any(|segment| segment.image.is_some()));
+
+        let json = serde_json::to_string(&segments).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<Segment>>(&json).unwrap(),
+            segments
+        );
+    }
+}
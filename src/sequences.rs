@@ -1,47 +1,302 @@
 use super::{
+    control_string::{self, Dcs},
     cursor::CharCursor,
-    graphic_rendition::{self, Sgr},
+    diagnostics::{self, ParseErrorKind},
+    graphic_rendition::{self, Sgr, SgrParams},
+    osc::Osc,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
 };
 
-fn cursor_skip_space(cursor: &mut CharCursor) {
+/// Read the intermediate characters.
+fn cursor_read_intermediates<'a>(cursor: &mut CharCursor<'a>) -> &'a str {
+    let start = cursor.position();
     // skip: !"#$%&'()*+,-./ (SPACE)
     cursor.read_while(|c| matches!(c, '\u{0020}'..='\u{002f}'));
+    cursor.get(start..cursor.position()).unwrap_or_default()
+}
+
+/// Whether the character is an 8-bit (C1) control code.
+pub(crate) fn is_c1_control(c: char) -> bool {
+    matches!(c, '\u{0080}'..='\u{009f}')
+}
+
+/// Options for parsing escape sequences.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let options = ParseOptions {
+///     c1_controls: true,
+/// };
+/// let markers = yew_ansi::get_markers_with_options("\u{009b}1mbold", options).collect::<Vec<_>>();
+/// assert_eq!(
+///     markers,
+///     vec![
+///         Marker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+///         Marker::Text("bold"),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    /// Treat the 8-bit C1 control codes (U+0080 to U+009F) as their 7-bit equivalents.
+    ///
+    /// For example U+009B is the same as `ESC [` (CSI) and U+009C is the same as `ESC \` (ST).
+    /// This is off by default because these code points are sometimes used as regular characters.
+    pub c1_controls: bool,
+}
+impl ParseOptions {
+    /// Whether the character starts an escape sequence.
+    pub(crate) fn is_introducer(self, c: char) -> bool {
+        c == Escape::ESC || (self.c1_controls && is_c1_control(c))
+    }
+}
+
+/// Escape sequence which isn't supported.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownSequence {
+    /// The entire sequence as it appears in the input.
+    pub raw: String,
+    /// Parameters separated by `;`.
+    pub params: Vec<String>,
+    /// Intermediate characters (U+0020 to U+002F) in front of the final byte.
+    pub intermediates: String,
+    /// Character which ends the sequence.
+    ///
+    /// This is `None` for invalid sequences, which end before the character that makes them invalid,
+    /// and for unsupported operating system commands.
+    pub final_byte: Option<char>,
+}
+impl UnknownSequence {
+    /// Create the sequence from the input that was read since `start`.
+    fn read_since(
+        cursor: &CharCursor,
+        start: usize,
+        params: &[&str],
+        intermediates: &str,
+        final_byte: Option<char>,
+    ) -> Self {
+        Self {
+            raw: cursor
+                .get(start..cursor.position())
+                .unwrap_or_default()
+                .to_owned(),
+            params: params.iter().map(|&param| param.to_owned()).collect(),
+            intermediates: intermediates.to_owned(),
+            final_byte,
+        }
+    }
 }
 
 /// ANSI Escape Sequence.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Escape {
     Csi(Csi),
+    Osc(Osc),
+    Dcs(Dcs),
+    /// Application Program Command (APC), containing its payload.
+    Apc(String),
+    /// Privacy Message (PM), containing its payload.
+    Pm(String),
+    /// Start of String (SOS), containing the string.
+    Sos(String),
+    /// Any other escape sequence, including invalid ones.
+    Unknown(UnknownSequence),
 }
 impl Escape {
     const ESC: char = '\u{001b}';
+    const APC: char = '_';
+    const PM: char = '^';
+    const SOS: char = 'X';
+
+    /// Read the introducer of the escape sequence.
+    /// Returns the intermediate characters and the final byte of the 7-bit form of the introducer.
+    fn read_introducer<'a>(
+        cursor: &mut CharCursor<'a>,
+        options: ParseOptions,
+    ) -> Option<(&'a str, Option<char>)> {
+        if options.c1_controls {
+            if let Some(c) = cursor.read_if(is_c1_control) {
+                // C1 codes are ESC followed by the character 0x40 below them
+                return Some(("", char::from_u32(c as u32 - 0x40)));
+            }
+        }
 
-    fn parse(cursor: &mut CharCursor) -> Option<Self> {
         cursor.read_char(Self::ESC)?;
-        cursor_skip_space(cursor);
-        if Csi::peek(cursor) {
-            Csi::parse(cursor).map(Self::Csi)
-        } else {
-            None
+        let intermediates = cursor_read_intermediates(cursor);
+        // 0–9:;<=>?@A–Z[\]^_`a–z{|}~
+        let final_byte = cursor.read_if(|c| matches!(c, '\u{0030}'..='\u{007e}'));
+        Some((intermediates, final_byte))
+    }
+
+    /// Parse the escape sequence at the cursor.
+    /// Problems with the sequence are added to `errors`.
+    fn parse(
+        cursor: &mut CharCursor,
+        options: ParseOptions,
+        errors: &mut Vec<ParseErrorKind>,
+    ) -> Option<Self> {
+        let start = cursor.position();
+        let (intermediates, final_byte) = Self::read_introducer(cursor, options)?;
+        let escape = match final_byte {
+            Some(Csi::START) => Self::Csi(Csi::parse(cursor, start, errors)),
+            Some(Osc::START) => {
+                let payload = Osc::read_payload(cursor, options);
+                if payload.is_none() {
+                    errors.push(ParseErrorKind::UnterminatedSequence);
+                }
+                match payload.and_then(Osc::from_payload) {
+                    Some(osc) => Self::Osc(osc),
+                    None => {
+                        let params = payload
+                            .map(|payload| payload.split(';').collect::<Vec<_>>())
+                            .unwrap_or_default();
+                        Self::Unknown(UnknownSequence::read_since(
+                            cursor, start, &params, "", None,
+                        ))
+                    }
+                }
+            }
+            Some(Dcs::START) => match Dcs::parse(cursor, options, errors) {
+                Some(dcs) => Self::Dcs(dcs),
+                None => Self::Unknown(UnknownSequence::read_since(cursor, start, &[], "", None)),
+            },
+            Some(c @ (Self::APC | Self::PM | Self::SOS)) => {
+                match control_string::read_payload(cursor, options, false, false) {
+                    Some(payload) => {
+                        let payload = payload.to_owned();
+                        match c {
+                            Self::APC => Self::Apc(payload),
+                            Self::PM => Self::Pm(payload),
+                            _ => Self::Sos(payload),
+                        }
+                    }
+                    None => {
+                        errors.push(ParseErrorKind::UnterminatedSequence);
+                        Self::Unknown(UnknownSequence::read_since(cursor, start, &[], "", None))
+                    }
+                }
+            }
+            _ => {
+                if final_byte.is_none() {
+                    errors.push(ParseErrorKind::UnterminatedSequence);
+                }
+                Self::Unknown(UnknownSequence::read_since(
+                    cursor,
+                    start,
+                    &[],
+                    intermediates,
+                    final_byte,
+                ))
+            }
+        };
+        Some(escape)
+    }
+}
+/// Writes the canonical form of the escape sequence.
+/// String sequences are always terminated with ST (`ESC \\`).
+///
+/// ```
+/// # use yew_ansi::*;
+/// let escape = Escape::Csi(Csi::Sgr(vec![Sgr::Bold, Sgr::ColorFgName(ColorName::Red)]));
+/// assert_eq!(escape.to_string(), "\u{001b}[1;31m");
+/// assert_eq!(Escape::Apc("data".to_owned()).to_string(), "\u{001b}_data\u{001b}\\");
+/// ```
+impl Display for Escape {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (introducer, payload) = match self {
+            Self::Csi(csi) => return csi.fmt(f),
+            Self::Osc(osc) => return osc.fmt(f),
+            Self::Dcs(dcs) => return dcs.fmt(f),
+            Self::Apc(payload) => (Self::APC, payload),
+            Self::Pm(payload) => (Self::PM, payload),
+            Self::Sos(payload) => (Self::SOS, payload),
+            Self::Unknown(unknown) => return f.write_str(&unknown.raw),
+        };
+        write!(f, "\u{001b}{}{}\u{001b}\\", introducer, payload)
+    }
+}
+
+/// Part of a line or the display affected by an erase sequence.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EraseMode {
+    /// From the cursor to the end (inclusive).
+    ToEnd,
+    /// From the start to the cursor (inclusive).
+    ToStart,
+    /// Everything.
+    All,
+}
+impl EraseMode {
+    fn from_param(param: usize) -> Option<Self> {
+        match param {
+            0 => Some(Self::ToEnd),
+            1 => Some(Self::ToStart),
+            2 => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    fn param(self) -> usize {
+        match self {
+            Self::ToEnd => 0,
+            Self::ToStart => 1,
+            Self::All => 2,
         }
     }
 }
 
 /// Control sequence.
+///
+/// Counts are always at least 1 and positions are 1-based, just like in the sequence itself.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Csi {
     Sgr(Vec<Sgr>),
+    /// Cursor Up (CUU).
+    CursorUp(usize),
+    /// Cursor Down (CUD).
+    CursorDown(usize),
+    /// Cursor Forward (CUF).
+    CursorForward(usize),
+    /// Cursor Back (CUB).
+    CursorBack(usize),
+    /// Cursor Next Line (CNL).
+    CursorNextLine(usize),
+    /// Cursor Previous Line (CPL).
+    CursorPreviousLine(usize),
+    /// Cursor Horizontal Absolute (CHA).
+    CursorColumn(usize),
+    /// Cursor Position (CUP).
+    CursorPosition {
+        row: usize,
+        column: usize,
+    },
+    /// Erase in Display (ED).
+    ///
+    /// Erasing the scrollback (`CSI 3 J`) is treated like [`EraseMode::All`].
+    EraseInDisplay(EraseMode),
+    /// Erase in Line (EL).
+    EraseInLine(EraseMode),
+    /// Any other control sequence, or one with invalid parameters.
+    Unknown(UnknownSequence),
 }
 impl Csi {
     const START: char = '[';
 
-    fn peek(cursor: &mut CharCursor) -> bool {
-        cursor.peek_char(Self::START)
-    }
-
-    fn read_params<'a>(cursor: &mut CharCursor<'a>) -> Option<(char, Vec<&'a str>)> {
+    /// Read the parameters, the intermediate characters, and the final byte.
+    /// The final byte is `None` if the sequence is invalid.
+    pub(crate) fn read_params<'a>(
+        cursor: &mut CharCursor<'a>,
+    ) -> (Vec<&'a str>, &'a str, Option<char>) {
         let mut start = cursor.position();
         let mut end = start;
         let mut params = Vec::new();
@@ -67,39 +322,118 @@ impl Csi {
             params.push(start..end);
         }
 
-        cursor_skip_space(cursor);
+        let params = params.drain(..).map(|r| cursor.get(r).unwrap()).collect();
+        let intermediates = cursor_read_intermediates(cursor);
 
         // read method name
-        match cursor.read()? {
-            c @ '\u{0040}'..='\u{007e}' => {
-                let params = params.drain(..).map(|r| cursor.get(r).unwrap()).collect();
-                Some((c, params))
-            }
-            _ => None,
+        let method = cursor.read_if(|c| matches!(c, '\u{0040}'..='\u{007e}'));
+        (params, intermediates, method)
+    }
+
+    /// Get the numeric parameter at the given index.
+    /// Missing and empty parameters result in the default value.
+    fn param_or(params: &[&str], index: usize, default: usize) -> Result<usize, ParseErrorKind> {
+        match params.get(index).copied().unwrap_or_default() {
+            "" => Ok(default),
+            p => diagnostics::parse_number(p),
         }
     }
 
-    fn parse(cursor: &mut CharCursor) -> Option<Self> {
-        cursor.read_char(Self::START)?;
-        let (method, params) = Self::read_params(cursor)?;
-        match method {
-            'm' => {
-                let params: Vec<usize> = params
-                    .iter()
-                    .map(|p| p.parse())
-                    .collect::<Result<_, _>>()
-                    .ok()?;
-                let sgrs = graphic_rendition::parse_sgrs(params.iter().copied());
-                Some(Self::Sgr(sgrs))
-            }
-            _ => None,
+    /// Get the numeric parameter at the given index.
+    /// Missing, empty, and zero parameters all result in the default value 1.
+    fn param_count(params: &[&str], index: usize) -> Result<usize, ParseErrorKind> {
+        Self::param_or(params, index, 1).map(|n| n.max(1))
+    }
+
+    fn erase_mode(params: &[&str]) -> Result<EraseMode, ParseErrorKind> {
+        let param = Self::param_or(params, 0, 0)?;
+        EraseMode::from_param(param).ok_or(ParseErrorKind::InvalidParameter)
+    }
+
+    /// Parse the control sequence following the introducer.
+    /// `start` is the position of the introducer.
+    fn parse(cursor: &mut CharCursor, start: usize, errors: &mut Vec<ParseErrorKind>) -> Self {
+        let (params, intermediates, method) = Self::read_params(cursor);
+        if method.is_none() {
+            errors.push(ParseErrorKind::UnterminatedSequence);
         }
+        method
+            .and_then(|method| Self::from_params(method, &params, errors))
+            .unwrap_or_else(|| {
+                Self::Unknown(UnknownSequence::read_since(
+                    cursor,
+                    start,
+                    &params,
+                    intermediates,
+                    method,
+                ))
+            })
+    }
+
+    /// Parse a supported control sequence.
+    /// Returns `None` if the sequence isn't supported or its parameters are invalid.
+    fn from_params(
+        method: char,
+        params: &[&str],
+        errors: &mut Vec<ParseErrorKind>,
+    ) -> Option<Self> {
+        let count = || Self::param_count(params, 0);
+        let csi = match method {
+            'A' => count().map(Self::CursorUp),
+            'B' => count().map(Self::CursorDown),
+            'C' => count().map(Self::CursorForward),
+            'D' => count().map(Self::CursorBack),
+            'E' => count().map(Self::CursorNextLine),
+            'F' => count().map(Self::CursorPreviousLine),
+            'G' => count().map(Self::CursorColumn),
+            'H' | 'f' => Self::param_count(params, 0).and_then(|row| {
+                Ok(Self::CursorPosition {
+                    row,
+                    column: Self::param_count(params, 1)?,
+                })
+            }),
+            // erasing the scrollback is treated like erasing everything
+            'J' if Self::param_or(params, 0, 0) == Ok(3) => {
+                Ok(Self::EraseInDisplay(EraseMode::All))
+            }
+            'J' => Self::erase_mode(params).map(Self::EraseInDisplay),
+            'K' => Self::erase_mode(params).map(Self::EraseInLine),
+            'm' => return graphic_rendition::parse_sgrs(params, errors).map(Self::Sgr),
+            _ => return None,
+        };
+        csi.map_err(|kind| errors.push(kind)).ok()
+    }
+}
+/// Writes the canonical form of the control sequence.
+/// Unknown sequences are written exactly like they appeared in the input.
+/// A SGR sequence without any parameters doesn't change anything, so nothing is written for it.
+impl Display for Csi {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (param, method) = match self {
+            Self::Sgr(sgrs) if sgrs.is_empty() => return Ok(()),
+            Self::Sgr(sgrs) => return write!(f, "\u{001b}[{}m", SgrParams(sgrs)),
+            Self::CursorUp(n) => (n, 'A'),
+            Self::CursorDown(n) => (n, 'B'),
+            Self::CursorForward(n) => (n, 'C'),
+            Self::CursorBack(n) => (n, 'D'),
+            Self::CursorNextLine(n) => (n, 'E'),
+            Self::CursorPreviousLine(n) => (n, 'F'),
+            Self::CursorColumn(n) => (n, 'G'),
+            Self::CursorPosition { row, column } => {
+                return write!(f, "\u{001b}[{};{}H", row, column)
+            }
+            Self::EraseInDisplay(mode) => return write!(f, "\u{001b}[{}J", mode.param()),
+            Self::EraseInLine(mode) => return write!(f, "\u{001b}[{}K", mode.param()),
+            Self::Unknown(unknown) => return f.write_str(&unknown.raw),
+        };
+        write!(f, "\u{001b}[{}{}", param, method)
     }
 }
 
 /// Read the next sequence in the given slice.
 /// Returns the content before the escape sequence, the escape sequence itself, and everything following it.
-/// The escape sequence can be `None` if it's invalid.
+/// The escape sequence is only `None` if the slice doesn't contain one,
+/// sequences which aren't supported are returned as [`Escape::Unknown`] or [`Csi::Unknown`].
 /// If the slice doesn't contain an escape sequence the entire string slice will be returned as the first item.
 ///
 /// ```
@@ -115,77 +449,243 @@ impl Csi {
 /// assert_eq!(post, "World");
 /// ```
 pub fn read_next_sequence(s: &str) -> (&str, Option<Escape>, &str) {
-    s.find(Escape::ESC).map_or((s, None, ""), |index| {
-        let (pre, post) = s.split_at(index);
+    read_next_sequence_with_options(s, ParseOptions::default())
+}
+
+/// Same as [`read_next_sequence`] but with the given [`ParseOptions`].
+pub fn read_next_sequence_with_options(
+    s: &str,
+    options: ParseOptions,
+) -> (&str, Option<Escape>, &str) {
+    read_next_sequence_with_errors(s, options, &mut Vec::new())
+}
+
+/// Same as [`read_next_sequence_with_options`] but adds the problems with the sequence to `errors`.
+pub(crate) fn read_next_sequence_with_errors<'a>(
+    s: &'a str,
+    options: ParseOptions,
+    errors: &mut Vec<ParseErrorKind>,
+) -> (&'a str, Option<Escape>, &'a str) {
+    s.find(|c| options.is_introducer(c))
+        .map_or((s, None, ""), |index| {
+            let (pre, post) = s.split_at(index);
 
-        let mut cursor = CharCursor::new(post);
-        let esc = Escape::parse(&mut cursor);
+            let mut cursor = CharCursor::new(post);
+            let esc = Escape::parse(&mut cursor, options, errors);
 
-        (pre, esc, cursor.remainder())
-    })
+            (pre, esc, cursor.remainder())
+        })
+}
+
+/// Find the start of the escape sequence at the end of the slice which is cut off.
+///
+/// Returns `None` if the slice doesn't end in the middle of an escape sequence.
+pub(crate) fn incomplete_sequence_start(s: &str, options: ParseOptions) -> Option<usize> {
+    // the sequence is incomplete if it only ended because it reached the end of the slice.
+    let is_incomplete = |start: usize| {
+        let mut cursor = CharCursor::new(&s[start..]);
+        let mut errors = Vec::new();
+        Escape::parse(&mut cursor, options, &mut errors);
+        cursor.remainder().is_empty() && errors.contains(&ParseErrorKind::UnterminatedSequence)
+    };
+
+    // escape sequences can't contain an introducer, so only the last one can be incomplete.
+    // The exception are device control strings, which can contain escaped ESCs.
+    let is_introducer = |c| options.is_introducer(c);
+    let last = s.rfind(is_introducer)?;
+    let mut anchor = if s.ends_with(Escape::ESC) {
+        s[..last].rfind(is_introducer)
+    } else {
+        Some(last)
+    };
+    while let Some(index) = anchor {
+        let escaped =
+            s[index..].starts_with("\u{001b}\u{001b}") || s[..index].ends_with(Escape::ESC);
+        if !escaped {
+            break;
+        }
+        anchor = s[..index].rfind(is_introducer);
+    }
+    if let Some(start) = anchor.filter(|&start| start < last) {
+        let mut cursor = CharCursor::new(&s[start..]);
+        let is_dcs = matches!(
+            Escape::read_introducer(&mut cursor, options),
+            Some((_, Some(Dcs::START)))
+        );
+        if is_dcs && is_incomplete(start) {
+            return Some(start);
+        }
+    }
+
+    if s.ends_with(Escape::ESC) {
+        // the trailing ESC might be the start of the string terminator of the previous sequence.
+        let previous = s[..last]
+            .rfind(is_introducer)
+            .filter(|&previous| is_incomplete(previous));
+        return Some(previous.unwrap_or(last));
+    }
+
+    Some(last).filter(|&last| is_incomplete(last))
+}
+
+/// Get a function which tells whether a character can end the incomplete escape sequence.
+///
+/// `sequence` is the incomplete sequence found by [`incomplete_sequence_start`].
+/// Appending a character for which the function returns `false` leaves the sequence incomplete,
+/// so the sequence doesn't need to be parsed again until one of the other characters shows up.
+/// If the sequence ends with ESC, which might be the start of the string terminator, every character can end it.
+pub(crate) fn incomplete_sequence_end(
+    sequence: &str,
+    options: ParseOptions,
+) -> impl Fn(char) -> bool {
+    let mut cursor = CharCursor::new(sequence);
+    let final_byte = Escape::read_introducer(&mut cursor, options).and_then(|(_, c)| c);
+    let trailing_esc = sequence.len() > 1 && sequence.ends_with(Escape::ESC);
+    move |c| {
+        if trailing_esc {
+            return true;
+        }
+        match final_byte {
+            Some(Osc::START | Escape::APC | Escape::PM | Escape::SOS) => {
+                c == '\u{0007}' || options.is_introducer(c)
+            }
+            // the header is ended by any character that isn't part of it
+            Some(Dcs::START) => options.is_introducer(c) || !matches!(c, ' '..='~'),
+            // parameters and intermediates
+            Some(Csi::START) => !matches!(c, '\u{0020}'..='\u{003f}'),
+            Some(_) => true,
+            // intermediates
+            None => !matches!(c, '\u{0020}'..='\u{002f}'),
+        }
+    }
 }
 
 /// Parts of a string containing ANSI escape sequences.
+///
+/// Use [`OwnedMarker`] to keep the marker around without the string or to deserialize it.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Marker<'a> {
     /// Raw text without any escape sequences.
     Text(&'a str),
     /// Parsed escape sequence.
     Sequence(Escape),
 }
+impl Marker<'_> {
+    /// Copy the text so that the marker no longer borrows from the string.
+    pub fn into_owned(self) -> OwnedMarker {
+        match self {
+            Self::Text(text) => OwnedMarker::Text(text.to_owned()),
+            Self::Sequence(escape) => OwnedMarker::Sequence(escape),
+        }
+    }
+}
+
+/// Owned version of [`Marker`].
+///
+/// It's serialized exactly like a [`Marker`], so serialized markers can be deserialized as `OwnedMarker`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename = "Marker")
+)]
+pub enum OwnedMarker {
+    /// Raw text without any escape sequences.
+    Text(String),
+    /// Parsed escape sequence.
+    Sequence(Escape),
+}
+impl From<Marker<'_>> for OwnedMarker {
+    fn from(marker: Marker) -> Self {
+        marker.into_owned()
+    }
+}
 
 /// Iterator yielding markers in a string.
 ///
 /// Each item is a [`Marker`].
 ///
-/// Returned by [`get_markers`].
+/// Returned by [`get_markers`] and [`get_markers_with_options`].
 #[must_use = "iterators are lazy and do nothing unless consumed"]
 #[derive(Clone, Debug)]
 pub struct MarkerIter<'a> {
-    remaining: &'a str,
-    buf: Option<Marker<'a>>,
+    s: &'a str,
+    pos: usize,
+    options: ParseOptions,
+    buf: Option<(Marker<'a>, Range<usize>)>,
 }
 impl<'a> MarkerIter<'a> {
-    fn new(s: &'a str) -> Self {
+    fn new(s: &'a str, options: ParseOptions) -> Self {
         Self {
-            remaining: s,
+            s,
+            pos: 0,
+            options,
             buf: None,
         }
     }
-}
-impl<'a> Iterator for MarkerIter<'a> {
-    type Item = Marker<'a>;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Also yield the byte range of each marker in the string.
+    pub fn with_spans(self) -> SpannedMarkerIter<'a> {
+        SpannedMarkerIter(self)
+    }
+
+    fn next_spanned(&mut self) -> Option<(Marker<'a>, Range<usize>)> {
         // handle the marker that might have been buffered by last iteration
         if let Some(marker) = self.buf.take() {
             return Some(marker);
         }
 
-        while !self.remaining.is_empty() {
-            let (pre, esc, post) = read_next_sequence(&self.remaining);
-            self.remaining = post;
+        while self.pos < self.s.len() {
+            let remaining = &self.s[self.pos..];
+            let (pre, esc, post) = read_next_sequence_with_options(remaining, self.options);
+            let start = self.pos;
+            let text_end = start + pre.len();
+            self.pos += remaining.len() - post.len();
 
-            let esc_marker = esc.map(Marker::Sequence);
+            let esc_marker = esc.map(|esc| (Marker::Sequence(esc), text_end..self.pos));
 
             if pre.is_empty() {
                 if let Some(marker) = esc_marker {
                     return Some(marker);
                 }
 
-                // nothing to yield right now, this either means we're at the end or we just skipped over an invalid escape sequence.
+                // nothing to yield right now, this means we're at the end.
                 // explicit "continue" here to make it clear.
                 continue;
             } else {
                 // store the escape code for the next iteration
                 self.buf = esc_marker;
-                return Some(Marker::Text(pre));
+                return Some((Marker::Text(pre), start..text_end));
             }
         }
 
         None
     }
 }
+impl<'a> Iterator for MarkerIter<'a> {
+    type Item = Marker<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|(marker, _)| marker)
+    }
+}
+
+/// Iterator yielding markers in a string along with their byte range.
+///
+/// Each item is a tuple containing the [`Marker`] and the [`Range`] of the string it was read from.
+///
+/// Returned by [`get_markers_with_spans`] and [`MarkerIter::with_spans`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct SpannedMarkerIter<'a>(MarkerIter<'a>);
+impl<'a> Iterator for SpannedMarkerIter<'a> {
+    type Item = (Marker<'a>, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_spanned()
+    }
+}
 
 /// Iterate over all [`Marker`]s in given string.
 ///
@@ -208,18 +708,60 @@ impl<'a> Iterator for MarkerIter<'a> {
 ///     ]
 /// );
 /// ```
-pub fn get_markers(s: &str) -> MarkerIter {
-    MarkerIter::new(s)
+pub fn get_markers(s: &str) -> MarkerIter<'_> {
+    MarkerIter::new(s, ParseOptions::default())
+}
+
+/// Iterate over all [`Marker`]s in given string using the given [`ParseOptions`].
+pub fn get_markers_with_options(s: &str, options: ParseOptions) -> MarkerIter<'_> {
+    MarkerIter::new(s, options)
+}
+
+/// Iterate over all [`Marker`]s in given string along with the byte range they cover.
+///
+/// Use [`MarkerIter::with_spans`] to get the spans with custom [`ParseOptions`].
+///
+/// ```
+/// # use yew_ansi::*;
+/// let s = "Hello \u{001b}[1mWorld";
+/// let markers = yew_ansi::get_markers_with_spans(s).collect::<Vec<_>>();
+/// assert_eq!(
+///     markers,
+///     vec![
+///         (Marker::Text("Hello "), 0..6),
+///         (Marker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))), 6..10),
+///         (Marker::Text("World"), 10..15),
+///     ]
+/// );
+/// assert_eq!(&s[6..10], "\u{001b}[1m");
+/// ```
+pub fn get_markers_with_spans(s: &str) -> SpannedMarkerIter<'_> {
+    get_markers(s).with_spans()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::graphic_rendition::ColorName;
+    use crate::{
+        graphic_rendition::{ColorEffect, ColorName, SgrEffect, UnderlineStyle},
+        osc::Hyperlink,
+    };
 
     fn parse(s: &str) -> Option<Escape> {
         let s = s.replace("CSI ", "\u{001b} [");
-        Escape::parse(&mut CharCursor::new(&s))
+        Escape::parse(
+            &mut CharCursor::new(&s),
+            ParseOptions::default(),
+            &mut Vec::new(),
+        )
+    }
+
+    fn is_unknown_csi(s: &str) -> bool {
+        matches!(parse(s), Some(Escape::Csi(Csi::Unknown(_))))
+    }
+
+    fn incomplete(s: &str) -> Option<usize> {
+        incomplete_sequence_start(s, ParseOptions::default())
     }
 
     fn parse_sgr(s: &str) -> Vec<Sgr> {
@@ -239,6 +781,458 @@ mod tests {
             parse_sgr("CSI 32;1m"),
             vec![Sgr::ColorFgName(ColorName::Green), Sgr::Bold]
         );
+        assert_eq!(parse_sgr("CSI ;1m"), vec![Sgr::Reset, Sgr::Bold]);
+        assert_eq!(parse_sgr("CSI m"), vec![Sgr::Reset]);
+        assert_eq!(
+            parse_sgr("CSI 2;7;9;21;53m"),
+            vec![
+                Sgr::Faint,
+                Sgr::Inverse,
+                Sgr::Strikethrough,
+                Sgr::StyledUnderline(UnderlineStyle::Double),
+                Sgr::Overline
+            ]
+        );
+        // unsupported parameters are skipped
+        assert_eq!(parse_sgr("CSI 1;73;3m"), vec![Sgr::Bold, Sgr::Italic]);
+    }
+
+    #[test]
+    fn parsing_sub_params() {
+        assert_eq!(
+            parse_sgr("CSI 38:2::255:128:0m"),
+            vec![Sgr::ColorFgRgb(0xff8000)]
+        );
+        assert_eq!(
+            parse_sgr("CSI 48:2:1:0:0:255;1m"),
+            vec![Sgr::ColorBgRgb(0x0000ff), Sgr::Bold]
+        );
+        assert_eq!(
+            parse_sgr("CSI 38:2:255:128:0m"),
+            vec![Sgr::ColorFgRgb(0xff8000)]
+        );
+        assert_eq!(
+            parse_sgr("CSI 38:5:1;48;5;9m"),
+            vec![
+                Sgr::ColorFgName(ColorName::Red),
+                Sgr::ColorBgNameBright(ColorName::Red)
+            ]
+        );
+        assert_eq!(
+            parse_sgr("CSI 4:3m"),
+            vec![Sgr::StyledUnderline(UnderlineStyle::Curly)]
+        );
+        assert_eq!(
+            parse_sgr("CSI 4:0m"),
+            vec![Sgr::StyledUnderline(UnderlineStyle::None)]
+        );
+        assert_eq!(
+            parse_sgr("CSI 58:2::255:0:0;58;5;2;59m"),
+            vec![
+                Sgr::ColorUnderlineRgb(0xff0000),
+                Sgr::ColorUnderlineName(ColorName::Green),
+                Sgr::ResetColorUnderline
+            ]
+        );
+        assert!(is_unknown_csi("CSI 38:2:99999999999999999999999:0:0m"));
+        // components out of range are ignored instead of producing a different colour
+        assert_eq!(parse_sgr("CSI 38;2;300;0;0;1m"), vec![Sgr::Bold]);
+    }
+
+    #[test]
+    fn parsing_cursor() {
+        assert_eq!(parse("CSI A"), Some(Escape::Csi(Csi::CursorUp(1))));
+        assert_eq!(parse("CSI 0B"), Some(Escape::Csi(Csi::CursorDown(1))));
+        assert_eq!(parse("CSI 12G"), Some(Escape::Csi(Csi::CursorColumn(12))));
+        assert_eq!(
+            parse("CSI ;5H"),
+            Some(Escape::Csi(Csi::CursorPosition { row: 1, column: 5 }))
+        );
+        assert_eq!(
+            parse("CSI 3;4f"),
+            Some(Escape::Csi(Csi::CursorPosition { row: 3, column: 4 }))
+        );
+        assert!(is_unknown_csi("CSI xA"));
+    }
+
+    #[test]
+    fn parsing_erase() {
+        assert_eq!(
+            parse("CSI K"),
+            Some(Escape::Csi(Csi::EraseInLine(EraseMode::ToEnd)))
+        );
+        assert_eq!(
+            parse("CSI 1K"),
+            Some(Escape::Csi(Csi::EraseInLine(EraseMode::ToStart)))
+        );
+        assert_eq!(
+            parse("CSI 2J"),
+            Some(Escape::Csi(Csi::EraseInDisplay(EraseMode::All)))
+        );
+        assert_eq!(
+            parse("CSI 3J"),
+            Some(Escape::Csi(Csi::EraseInDisplay(EraseMode::All)))
+        );
+        assert!(is_unknown_csi("CSI 3K"));
+    }
+
+    #[test]
+    fn incomplete_sequences() {
+        assert_eq!(incomplete("abc"), None);
+        assert_eq!(incomplete("abc\u{001b}"), Some(3));
+        assert_eq!(incomplete("abc\u{001b}[3"), Some(3));
+        assert_eq!(incomplete("abc\u{001b}[32m"), None);
+        assert_eq!(incomplete("\u{001b}[1mabc\u{001b}[32"), Some(7));
+        assert_eq!(incomplete("\u{001b}]0;title"), Some(0));
+        assert_eq!(incomplete("\u{001b}]0;title\u{001b}"), Some(0));
+        assert_eq!(incomplete("\u{001b}]0;title\u{001b}\\"), None);
+        assert_eq!(incomplete("\u{001b}[1m\u{001b}"), Some(4));
+        assert_eq!(incomplete("\u{001b}x"), None);
+        assert_eq!(incomplete("\u{001b}Pq#0;2;0;0;0"), Some(0));
+        assert_eq!(incomplete("\u{001b}_payload\u{001b}"), Some(0));
+        assert_eq!(incomplete("\u{001b}_payload\u{001b}\\"), None);
+        assert_eq!(incomplete("a\u{001b}Ptmux;\u{001b}\u{001b}[1m"), Some(1));
+        assert_eq!(
+            incomplete("a\u{001b}Ptmux;\u{001b}\u{001b}[1m\u{001b}\u{001b}"),
+            Some(1)
+        );
+        assert_eq!(
+            incomplete("a\u{001b}Ptmux;\u{001b}\u{001b}[1m\u{001b}"),
+            Some(1)
+        );
+        assert_eq!(
+            incomplete("a\u{001b}Ptmux;\u{001b}\u{001b}[1m\u{001b}\\"),
+            None
+        );
+        assert_eq!(incomplete("\u{001b}[1m\u{001b}\u{001b}"), Some(5));
+
+        let c1 = ParseOptions { c1_controls: true };
+        assert_eq!(incomplete_sequence_start("abc\u{009b}3", c1), Some(3));
+        assert_eq!(incomplete_sequence_start("\u{009d}0;title", c1), Some(0));
+        assert_eq!(
+            incomplete_sequence_start("\u{009d}0;title\u{009c}", c1),
+            None
+        );
+        assert_eq!(
+            incomplete_sequence_start("\u{009d}0;title\u{009c}", ParseOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn marking_c1_controls() {
+        let input = "a\u{009b}1mb\u{009d}2;title\u{009c}c\u{0085}d";
+        let markers =
+            get_markers_with_options(input, ParseOptions { c1_controls: true }).collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                Marker::Text("a"),
+                Marker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+                Marker::Text("b"),
+                Marker::Sequence(Escape::Osc(Osc::SetTitle("title".to_owned()))),
+                Marker::Text("c"),
+                Marker::Sequence(Escape::Unknown(UnknownSequence {
+                    raw: "\u{0085}".to_owned(),
+                    final_byte: Some('E'),
+                    ..Default::default()
+                })),
+                Marker::Text("d"),
+            ]
+        );
+        assert_eq!(
+            get_markers(input).collect::<Vec<_>>(),
+            vec![Marker::Text(input)]
+        );
+    }
+
+    #[test]
+    fn marking_hyperlink() {
+        let markers =
+            get_markers("\u{001b}]8;;https://example.com\u{001b}\\link\u{001b}]8;;\u{0007}")
+                .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                Marker::Sequence(Escape::Osc(Osc::Hyperlink(Some(Hyperlink {
+                    id: None,
+                    uri: "https://example.com".to_owned()
+                })))),
+                Marker::Text("link"),
+                Marker::Sequence(Escape::Osc(Osc::Hyperlink(None))),
+            ]
+        )
+    }
+
+    #[test]
+    fn marking_string_sequences() {
+        let input = "a\u{001b}Ptmux;\u{001b}\u{001b}[31m red\u{001b}\\b\u{001b}_apc\u{0007}\u{001b}\\c\u{001b}^pm\u{001b}\\\u{001b}Xsos\u{001b}\\d";
+        let markers = get_markers(input).collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                Marker::Text("a"),
+                Marker::Sequence(Escape::Dcs(Dcs {
+                    params: Vec::new(),
+                    intermediates: String::new(),
+                    final_byte: 't',
+                    data: "mux;\u{001b}\u{001b}[31m red".to_owned(),
+                })),
+                Marker::Text("b"),
+                Marker::Sequence(Escape::Apc("apc\u{0007}".to_owned())),
+                Marker::Text("c"),
+                Marker::Sequence(Escape::Pm("pm".to_owned())),
+                Marker::Sequence(Escape::Sos("sos".to_owned())),
+                Marker::Text("d"),
+            ]
+        );
+
+        // an unterminated string is aborted by the next escape sequence
+        let markers = get_markers("\u{001b}_apc\u{001b}[1mbold").collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                Marker::Sequence(Escape::Unknown(UnknownSequence {
+                    raw: "\u{001b}_apc".to_owned(),
+                    ..Default::default()
+                })),
+                Marker::Sequence(Escape::Csi(Csi::Sgr(vec![Sgr::Bold]))),
+                Marker::Text("bold"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_wire_format() {
+        let input = "\u{001b}[1;38;2;1;2;3;4:3mbold\u{001b}]8;;https://example.com\u{0007}\u{001b}Pqdata\u{001b}\\\u{001b}[5X";
+        let markers = get_markers(input).collect::<Vec<_>>();
+        let owned = markers
+            .iter()
+            .cloned()
+            .map(OwnedMarker::from)
+            .collect::<Vec<_>>();
+
+        let json = serde_json::to_string(&markers).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<OwnedMarker>>(&json).unwrap(),
+            owned
+        );
+        let bytes = bincode::serialize(&markers).unwrap();
+        assert_eq!(bytes, bincode::serialize(&owned).unwrap());
+        assert_eq!(
+            bincode::deserialize::<Vec<OwnedMarker>>(&bytes).unwrap(),
+            owned
+        );
+
+        // the documented format
+        assert_eq!(
+            serde_json::to_string(&Marker::Sequence(Escape::Csi(Csi::Sgr(vec![
+                Sgr::Bold,
+                Sgr::ColorFgName(ColorName::Red),
+                Sgr::ColorBgRgb(0x010203),
+            ]))))
+            .unwrap(),
+            r#"{"Sequence":{"Csi":{"Sgr":["Bold",{"ColorFgName":"Red"},{"ColorBgRgb":66051}]}}}"#
+        );
+        let effect: SgrEffect =
+            serde_json::from_str(r#"{"bold":true,"fg":{"Name":"Green"}}"#).unwrap();
+        assert!(effect.bold);
+        assert_eq!(effect.fg, ColorEffect::Name(ColorName::Green));
+    }
+
+    #[test]
+    fn encoding() {
+        let canonical = [
+            "\u{001b}[1;2;3;4:3;4:2;5;6;7;8;9;53;22;23;24;25;27;28;29;55;0m",
+            "\u{001b}[31;92;44;105;39;49m",
+            "\u{001b}[38;2;1;2;3;48;2;255;0;128;58;2;0;0;0;58;5;1;58;5;9;59m",
+            "\u{001b}[3A\u{001b}[2B\u{001b}[4C\u{001b}[1D\u{001b}[2E\u{001b}[3F\u{001b}[7G",
+            "\u{001b}[3;4H\u{001b}[0J\u{001b}[1K\u{001b}[2J",
+            "\u{001b}]0;title\u{001b}\\\u{001b}]2;title\u{001b}\\\u{001b}]7;file:///tmp\u{001b}\\",
+            "\u{001b}]8;id=a;https://example.com\u{001b}\\\u{001b}]8;;\u{001b}\\",
+            "\u{001b}]9;body\u{001b}\\\u{001b}]777;notify;title;body\u{001b}\\",
+            "\u{001b}]1337;File=name=YS5wbmc=;width=2px;inline=1:AAEC\u{001b}\\",
+            "\u{001b}P1;2$qdata\u{001b}\\\u{001b}_apc\u{001b}\\\u{001b}^pm\u{001b}\\\u{001b}Xsos\u{001b}\\",
+            "\u{001b}[?25h\u{001b}(B",
+        ];
+        for input in canonical {
+            let encoded = get_markers(input)
+                .map(|marker| match marker {
+                    Marker::Sequence(esc) => esc.to_string(),
+                    Marker::Text(text) => panic!("unexpected text {:?}", text),
+                })
+                .collect::<String>();
+            assert_eq!(encoded, input);
+        }
+
+        // other forms are encoded in their canonical form
+        assert_eq!(
+            parse("CSI 38:2::1:2:3;38;5;1;4:1;1;;0m")
+                .unwrap()
+                .to_string(),
+            "\u{001b}[38;2;1;2;3;31;4:1;1;0;0m"
+        );
+        assert_eq!(parse("CSI 21m").unwrap().to_string(), "\u{001b}[4:2m");
+        assert_eq!(parse("CSI m").unwrap().to_string(), "\u{001b}[0m");
+        // invalid codes are skipped, which doesn't make them a reset
+        assert_eq!(parse("CSI 48m"), Some(Escape::Csi(Csi::Sgr(vec![]))));
+        assert_eq!(parse("CSI 48m").unwrap().to_string(), "");
+        let input = "\u{001b}[1m\u{001b}[48mbold";
+        let encoded = get_markers(input)
+            .map(|marker| match marker {
+                Marker::Sequence(esc) => esc.to_string(),
+                Marker::Text(text) => text.to_owned(),
+            })
+            .collect::<String>();
+        assert_eq!(encoded, "\u{001b}[1mbold");
+        assert_eq!(parse("CSI H").unwrap().to_string(), "\u{001b}[1;1H");
+        assert_eq!(
+            parse("\u{001b}]2;title\u{0007}").unwrap().to_string(),
+            "\u{001b}]2;title\u{001b}\\"
+        );
+    }
+
+    #[test]
+    fn transitions() {
+        let red = ColorEffect::Name(ColorName::Red);
+        let effects = [
+            SgrEffect::default(),
+            SgrEffect {
+                bold: true,
+                faint: true,
+                ..Default::default()
+            },
+            SgrEffect {
+                faint: true,
+                underline: UnderlineStyle::Curly,
+                underline_color: ColorEffect::Rgb(0x123456),
+                ..Default::default()
+            },
+            SgrEffect {
+                bold: true,
+                italic: true,
+                blink: true,
+                inverse: true,
+                hidden: true,
+                strikethrough: true,
+                overline: true,
+                fg: red.clone(),
+                bg: ColorEffect::NameBright(ColorName::Blue),
+                underline: UnderlineStyle::Double,
+                ..Default::default()
+            },
+            SgrEffect {
+                fg: red,
+                ..Default::default()
+            },
+        ];
+        for from in &effects {
+            for to in &effects {
+                let mut effect = from.clone();
+                effect.apply_sgrs(from.transition(to));
+                assert_eq!(&effect, to, "transition from {:?}", from);
+            }
+        }
+
+        let bold = &effects[1];
+        assert_eq!(bold.transition(bold), vec![]);
+        let italic = SgrEffect {
+            italic: true,
+            ..bold.clone()
+        };
+        assert_eq!(
+            italic.transition(&SgrEffect {
+                faint: false,
+                ..italic.clone()
+            }),
+            vec![Sgr::BoldOff, Sgr::Bold]
+        );
+        // resetting is shorter than turning off all attributes
+        assert_eq!(
+            effects[3].transition(&effects[4]),
+            vec![Sgr::Reset, Sgr::ColorFgName(ColorName::Red)]
+        );
+    }
+
+    #[test]
+    fn parsing_unknown() {
+        assert_eq!(
+            parse("CSI ?25h"),
+            Some(Escape::Csi(Csi::Unknown(UnknownSequence {
+                raw: "\u{001b} [?25h".to_owned(),
+                params: vec!["?25".to_owned()],
+                intermediates: "".to_owned(),
+                final_byte: Some('h'),
+            })))
+        );
+        assert_eq!(
+            parse("CSI 2 q"),
+            Some(Escape::Csi(Csi::Unknown(UnknownSequence {
+                raw: "\u{001b} [2 q".to_owned(),
+                params: vec!["2".to_owned()],
+                intermediates: " ".to_owned(),
+                final_byte: Some('q'),
+            })))
+        );
+        assert_eq!(
+            parse("\u{001b}(B"),
+            Some(Escape::Unknown(UnknownSequence {
+                raw: "\u{001b}(B".to_owned(),
+                params: Vec::new(),
+                intermediates: "(".to_owned(),
+                final_byte: Some('B'),
+            }))
+        );
+    }
+
+    #[test]
+    fn marking_unknown() {
+        let markers = get_markers("a\u{001b}]1337;SetMark\u{0007}b\u{001b}7c\u{001b}[1\nd")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            markers,
+            vec![
+                Marker::Text("a"),
+                Marker::Sequence(Escape::Unknown(UnknownSequence {
+                    raw: "\u{001b}]1337;SetMark\u{0007}".to_owned(),
+                    params: vec!["1337".to_owned(), "SetMark".to_owned()],
+                    ..Default::default()
+                })),
+                Marker::Text("b"),
+                Marker::Sequence(Escape::Unknown(UnknownSequence {
+                    raw: "\u{001b}7".to_owned(),
+                    final_byte: Some('7'),
+                    ..Default::default()
+                })),
+                Marker::Text("c"),
+                Marker::Sequence(Escape::Csi(Csi::Unknown(UnknownSequence {
+                    raw: "\u{001b}[1".to_owned(),
+                    params: vec!["1".to_owned()],
+                    ..Default::default()
+                }))),
+                Marker::Text("\nd"),
+            ]
+        )
+    }
+
+    #[test]
+    fn marking_spans() {
+        let input = "é\u{009b}1mü\u{001b}[1";
+        let options = ParseOptions { c1_controls: true };
+        let spans = get_markers_with_options(input, options)
+            .with_spans()
+            .map(|(_, span)| span)
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![0..2, 2..6, 6..8, 8..11]);
+        // spans cover the entire input
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| &input[span.clone()])
+                .collect::<String>(),
+            input
+        );
     }
 
     #[test]
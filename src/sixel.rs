@@ -0,0 +1,312 @@
+use crate::{control_string::Dcs, cursor::CharCursor};
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Number of colour registers.
+const PALETTE_SIZE: usize = 256;
+/// Maximum width and height of an image, anything outside of it is cut off.
+const MAX_SIZE: usize = 2048;
+/// Maximum number of pixels which are stored for an image, anything drawn beyond that is dropped.
+const MAX_PIXELS: usize = 1 << 20;
+
+/// Default colour registers of the VT340 as RGB percentages.
+const DEFAULT_PALETTE: [(usize, usize, usize); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Convert a percentage to a colour component.
+fn percent_to_component(percent: usize) -> u32 {
+    (percent.min(100) * 255 / 100) as u32
+}
+
+fn rgb_from_percent(r: usize, g: usize, b: usize) -> u32 {
+    (percent_to_component(r) << 16) | (percent_to_component(g) << 8) | percent_to_component(b)
+}
+
+/// Convert a sixel HLS colour to RGB.
+/// Unlike the usual HLS colour space, sixel hues start with blue at 0°.
+fn rgb_from_hls(hue: usize, lightness: usize, saturation: usize) -> u32 {
+    let h = ((hue + 240) % 360) as f64 / 60.0;
+    let l = lightness.min(100) as f64 / 100.0;
+    let s = saturation.min(100) as f64 / 100.0;
+
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as usize {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    let component = |c: f64| ((c + m) * 255.0).round() as u32;
+    (component(r) << 16) | (component(g) << 8) | component(b)
+}
+
+/// Read numeric parameters separated by `;`.
+/// Missing parameters are 0 and parameters which are too large saturate.
+fn read_params(cursor: &mut CharCursor) -> Vec<usize> {
+    let mut params = Vec::new();
+    loop {
+        let start = cursor.position();
+        cursor.read_while(|c| c.is_ascii_digit());
+        let digits = cursor.get(start..cursor.position()).unwrap_or_default();
+        params.push(match digits {
+            "" => 0,
+            digits => digits.parse().unwrap_or(usize::MAX),
+        });
+        if cursor.read_char(';').is_none() {
+            return params;
+        }
+    }
+}
+
+/// Image drawn with sixel graphics.
+///
+/// Pixels which aren't drawn are transparent and pixels are assumed to be square.
+/// Only the pixels which are drawn take up memory, so the size given by the raster attributes is free.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SixelImage {
+    width: usize,
+    height: usize,
+    /// Colours of each row up to the last pixel drawn in it.
+    rows: Vec<Vec<Option<u32>>>,
+}
+impl SixelImage {
+    /// Decode the image from the device control string.
+    /// Returns `None` if it doesn't contain sixel data (`DCS q`).
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let markers = yew_ansi::get_markers("\u{001b}Pq#1;2;100;0;0#1!3~\u{001b}\\").collect::<Vec<_>>();
+    /// let image = match &markers[0] {
+    ///     Marker::Sequence(Escape::Dcs(dcs)) => SixelImage::from_dcs(dcs).unwrap(),
+    ///     _ => unreachable!(),
+    /// };
+    /// assert_eq!((image.width(), image.height()), (3, 6));
+    /// assert_eq!(image.pixel(2, 5), Some(0xff0000));
+    /// ```
+    pub fn from_dcs(dcs: &Dcs) -> Option<Self> {
+        if dcs.final_byte == 'q' && dcs.intermediates.is_empty() {
+            Some(Self::decode(&dcs.data))
+        } else {
+            None
+        }
+    }
+
+    /// Decode the sixel data following the `DCS q` header.
+    pub fn decode(data: &str) -> Self {
+        let mut palette = [0; PALETTE_SIZE];
+        for (color, &(r, g, b)) in palette.iter_mut().zip(DEFAULT_PALETTE.iter()) {
+            *color = rgb_from_percent(r, g, b);
+        }
+
+        let mut rows: Vec<Vec<Option<u32>>> = Vec::new();
+        let mut pixel_count = 0;
+        let mut width = 0;
+        let mut height = 0;
+        let mut color = palette[0];
+        let (mut x, mut y): (usize, usize) = (0, 0);
+
+        let mut cursor = CharCursor::new(data);
+        while let Some(c) = cursor.read() {
+            let (repeat, c) = if c == '!' {
+                let repeat = read_params(&mut cursor)[0];
+                match cursor.read() {
+                    Some(c) => (repeat.max(1), c),
+                    None => break,
+                }
+            } else {
+                (1, c)
+            };
+
+            match c {
+                '?'..='~' => {
+                    let bits = c as u32 - '?' as u32;
+                    let end = x.saturating_add(repeat).min(MAX_SIZE);
+                    for bit in (0..6).filter(|bit| bits & (1 << bit) != 0) {
+                        let row_index = y + bit;
+                        if row_index >= MAX_SIZE || x >= end {
+                            continue;
+                        }
+                        if rows.len() <= row_index {
+                            rows.resize_with(row_index + 1, Vec::new);
+                        }
+                        let row = &mut rows[row_index];
+                        if row.len() < end {
+                            if pixel_count + end - row.len() > MAX_PIXELS {
+                                continue;
+                            }
+                            pixel_count += end - row.len();
+                            row.resize(end, None);
+                        }
+                        row[x..end].fill(Some(color));
+                        width = width.max(end);
+                        height = height.max(row_index + 1);
+                    }
+                    x = x.saturating_add(repeat);
+                }
+                '#' => {
+                    let params = read_params(&mut cursor);
+                    let index = params[0] % PALETTE_SIZE;
+                    if let [_, space, a, b, c, ..] = params[..] {
+                        palette[index] = match space {
+                            1 => rgb_from_hls(a, b, c),
+                            _ => rgb_from_percent(a, b, c),
+                        };
+                    }
+                    color = palette[index];
+                }
+                '$' => x = 0,
+                '-' => {
+                    x = 0;
+                    y = (y + 6).min(MAX_SIZE);
+                }
+                '"' => {
+                    // the aspect ratio is ignored, only the size is used
+                    if let [_, _, w, h, ..] = read_params(&mut cursor)[..] {
+                        width = width.max(w.min(MAX_SIZE));
+                        height = height.max(h.min(MAX_SIZE));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            width,
+            height,
+            rows,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the colour of the pixel as `0xRRGGBB`.
+    /// Returns `None` if the pixel is transparent or outside of the image.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u32> {
+        self.rows.get(y)?.get(x).copied().flatten()
+    }
+
+    /// Convert the image to SVG with one path per colour.
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let image = SixelImage::decode("#1;2;0;0;100@");
+    /// assert_eq!(
+    ///     image.to_svg(),
+    ///     r##"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1" shape-rendering="crispEdges"><path fill="#0000ff" d="M0 0h1v1h-1z"/></svg>"##
+    /// );
+    /// ```
+    pub fn to_svg(&self) -> String {
+        let mut paths: BTreeMap<u32, String> = BTreeMap::new();
+        for (y, row) in self.rows.iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let color = row[x];
+                let run = row[x..].iter().take_while(|&&c| c == color).count();
+                if let Some(color) = color {
+                    let _ = write!(
+                        paths.entry(color).or_default(),
+                        "M{} {}h{}v1h-{}z",
+                        x,
+                        y,
+                        run,
+                        run
+                    );
+                }
+                x += run;
+            }
+        }
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" shape-rendering="crispEdges">"#,
+            self.width, self.height
+        );
+        for (color, d) in paths {
+            let _ = write!(svg, r##"<path fill="#{:06x}" d="{}"/>"##, color, d);
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u32 = 0xff0000;
+    const BLUE: u32 = 0x0000ff;
+
+    #[test]
+    fn decoding() {
+        // two bands, the second one starts with a carriage return overwriting the first column
+        let image = SixelImage::decode("#1;2;100;0;0A!2B-#2;2;0;0;100~$#1?");
+        assert_eq!((image.width, image.height), (3, 12));
+        assert_eq!(image.pixel(0, 1), Some(RED));
+        assert_eq!(image.pixel(0, 0), None);
+        assert_eq!(image.pixel(2, 1), Some(RED));
+        assert_eq!(image.pixel(2, 2), None);
+        assert_eq!(image.pixel(0, 11), Some(BLUE));
+        assert_eq!(image.pixel(1, 6), None);
+        assert_eq!(image.pixel(3, 0), None);
+    }
+
+    #[test]
+    fn decoding_colors() {
+        // HLS with blue at 0°
+        let image = SixelImage::decode("#0;1;0;50;100~#0;1;120;50;100~#5~");
+        assert_eq!(image.pixel(0, 0), Some(BLUE));
+        assert_eq!(image.pixel(1, 0), Some(RED));
+        // default palette
+        assert_eq!(image.pixel(2, 0), Some(rgb_from_percent(20, 80, 80)));
+    }
+
+    #[test]
+    fn raster_attributes() {
+        let image = SixelImage::decode("\"1;1;4;2~");
+        assert_eq!((image.width, image.height), (4, 6));
+        assert_eq!(image.pixel(0, 0), Some(0));
+        assert_eq!(image.pixel(3, 0), None);
+
+        // the size alone doesn't allocate anything
+        let image = SixelImage::decode("\"1;1;2048;2048");
+        assert_eq!((image.width, image.height), (MAX_SIZE, MAX_SIZE));
+        assert!(image.rows.is_empty());
+    }
+
+    #[test]
+    fn size_is_limited() {
+        let image = SixelImage::decode("!99999999999999999999999~");
+        assert_eq!((image.width, image.height), (MAX_SIZE, 6));
+
+        let image = SixelImage::decode(&"!2048~-".repeat(400));
+        let pixel_count = image.rows.iter().map(Vec::len).sum::<usize>();
+        assert!(pixel_count <= MAX_PIXELS, "{}", pixel_count);
+        assert_eq!(image.pixel(0, 0), Some(0));
+        assert_eq!(image.pixel(0, MAX_SIZE - 1), None);
+    }
+}
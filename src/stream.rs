@@ -0,0 +1,327 @@
+use crate::{
+    bytes::{self, InvalidBytes},
+    graphic_rendition::SgrEffect,
+    sequences::{self, Csi, Escape, Marker, MarkerIter, ParseOptions},
+};
+
+/// Parser for text which arrives in chunks.
+///
+/// Escape sequences which are cut off at the end of a chunk are buffered until the next chunk completes them.
+/// Everything else is emitted right away.
+/// A sequence that grows beyond [`AnsiParser::MAX_BUFFERED`] bytes without being completed is emitted
+/// as an [`Escape::Unknown`] sequence and the rest of it as text.
+///
+/// ```
+/// # use yew_ansi::*;
+/// let mut parser = AnsiParser::new();
+/// assert_eq!(
+///     parser.feed("Hello \u{001b}[3").collect::<Vec<_>>(),
+///     vec![Marker::Text("Hello ")]
+/// );
+/// assert_eq!(
+///     parser.feed("2mWorld").collect::<Vec<_>>(),
+///     vec![
+///         Marker::Sequence(Escape::Csi(Csi::Sgr(vec![
+///             Sgr::ColorFgName(ColorName::Green),
+///         ]))),
+///         Marker::Text("World"),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AnsiParser {
+    buf: String,
+    /// Number of bytes at the start of `buf` which were already emitted.
+    parsed: usize,
+    /// Incomplete UTF-8 character at the end of the last chunk passed to [`AnsiParser::feed_bytes`].
+    pending: Vec<u8>,
+    effect: SgrEffect,
+    options: ParseOptions,
+}
+impl AnsiParser {
+    /// Maximum number of bytes of an incomplete escape sequence which are buffered.
+    ///
+    /// This is large enough for images sent inline.
+    pub const MAX_BUFFERED: usize = 4 * 1024 * 1024;
+
+    /// Create a new parser.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new parser using the given [`ParseOptions`].
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Get the effect at the end of the input fed to [`AnsiParser::feed_segments`] so far.
+    pub fn effect(&self) -> &SgrEffect {
+        &self.effect
+    }
+
+    /// Get the input which is buffered because it's an incomplete escape sequence.
+    pub fn buffered(&self) -> &str {
+        &self.buf[self.parsed..]
+    }
+
+    /// Discard the buffered input and reset the effect.
+    pub fn reset(&mut self) {
+        *self = Self::with_options(self.options);
+    }
+
+    fn push(&mut self, chunk: &str) -> &str {
+        self.buf.drain(..self.parsed);
+        // the buffer only contains the incomplete sequence,
+        // so there's no need to parse it again if the chunk can't end it.
+        let still_incomplete = !self.buf.is_empty()
+            && !chunk.contains(sequences::incomplete_sequence_end(&self.buf, self.options));
+        self.buf.push_str(chunk);
+        self.parsed = if still_incomplete {
+            0
+        } else {
+            sequences::incomplete_sequence_start(&self.buf, self.options).unwrap_or(self.buf.len())
+        };
+        if self.buf.len() - self.parsed > Self::MAX_BUFFERED {
+            self.parsed = self.buf.len();
+        }
+        &self.buf[..self.parsed]
+    }
+
+    /// Feed the next chunk of input to the parser.
+    ///
+    /// Returns an iterator over the [`Marker`]s which are complete.
+    /// This doesn't update the parser's effect.
+    pub fn feed(&mut self, chunk: &str) -> MarkerIter<'_> {
+        let options = self.options;
+        sequences::get_markers_with_options(self.push(chunk), options)
+    }
+
+    /// Feed the next chunk of raw bytes to the parser.
+    ///
+    /// Works like [`AnsiParser::feed`], but characters which are split across chunks are put back together.
+    /// Invalid bytes are handled according to `invalid_bytes`,
+    /// unlike [`get_markers_bytes`](crate::get_markers_bytes) this also applies to the ones inside escape sequences.
+    /// With [`ParseOptions::c1_controls`] enabled, the raw bytes 0x80 to 0x9F are recognised as C1 controls
+    /// as long as they aren't part of a UTF-8 character.
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let mut parser = AnsiParser::new();
+    /// assert_eq!(
+    ///     parser.feed_bytes(b"caf\xc3", InvalidBytes::Replace).collect::<Vec<_>>(),
+    ///     vec![Marker::Text("caf")]
+    /// );
+    /// assert_eq!(
+    ///     parser.feed_bytes(b"\xa9 \xff", InvalidBytes::Replace).collect::<Vec<_>>(),
+    ///     vec![Marker::Text("\u{e9} \u{fffd}")]
+    /// );
+    /// ```
+    pub fn feed_bytes(&mut self, chunk: &[u8], invalid_bytes: InvalidBytes) -> MarkerIter<'_> {
+        let joined;
+        let chunk = if self.pending.is_empty() {
+            chunk
+        } else {
+            joined = [std::mem::take(&mut self.pending).as_slice(), chunk].concat();
+            joined.as_slice()
+        };
+
+        let mut decoded = String::with_capacity(chunk.len());
+        let mut chunks = bytes::utf8_chunks(chunk).peekable();
+        while let Some((valid, invalid)) = chunks.next() {
+            decoded.push_str(valid);
+            if chunks.peek().is_none() && bytes::is_incomplete_char(invalid) {
+                // the character might be completed by the next chunk
+                self.pending.extend_from_slice(invalid);
+            } else if self.options.c1_controls && matches!(invalid, [0x80..=0x9f]) {
+                decoded.push(char::from(invalid[0]));
+            } else {
+                invalid_bytes.push_invalid(&mut decoded, invalid);
+            }
+        }
+
+        self.feed(&decoded)
+    }
+
+    /// Feed the next chunk of input to the parser.
+    ///
+    /// Returns an iterator over the SGR segments which are complete.
+    /// The effect carries over from previous chunks.
+    ///
+    /// ```
+    /// # use yew_ansi::*;
+    /// let mut parser = AnsiParser::new();
+    /// assert_eq!(parser.feed_segments("\u{001b}[1").count(), 0);
+    /// let bold = SgrEffect {
+    ///     bold: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     parser.feed_segments("mHello").collect::<Vec<_>>(),
+    ///     vec![(bold.clone(), "Hello")]
+    /// );
+    /// assert_eq!(
+    ///     parser.feed_segments(" World").collect::<Vec<_>>(),
+    ///     vec![(bold, " World")]
+    /// );
+    /// ```
+    pub fn feed_segments(&mut self, chunk: &str) -> StreamSegmentIter<'_> {
+        self.push(chunk);
+        StreamSegmentIter {
+            markers: sequences::get_markers_with_options(&self.buf[..self.parsed], self.options),
+            effect: &mut self.effect,
+        }
+    }
+}
+
+/// Iterator over the SGR segments in a chunk of input.
+///
+/// Each item is a tuple containing the [`SgrEffect`] and the [`&str`][str] it applies to.
+/// Consuming the iterator updates the effect of the parser.
+///
+/// Returned by [`AnsiParser::feed_segments`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Debug)]
+pub struct StreamSegmentIter<'a> {
+    markers: MarkerIter<'a>,
+    effect: &'a mut SgrEffect,
+}
+impl<'a> Iterator for StreamSegmentIter<'a> {
+    type Item = (SgrEffect, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.markers.next()? {
+                Marker::Text(text) => {
+                    return Some((self.effect.clone(), text));
+                }
+                Marker::Sequence(Escape::Csi(Csi::Sgr(sgrs))) => {
+                    self.effect.apply_sgrs(sgrs);
+                }
+                Marker::Sequence(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequences::OwnedMarker;
+    use std::str;
+
+    #[test]
+    fn split_sequences() {
+        let input =
+            "a\u{001b}[31mb\u{001b}]0;title\u{001b}\\c\u{001b}Pq#0\u{001b}\\\u{001b}(B\u{001b}[0md";
+        let expected = sequences::get_markers(input).collect::<Vec<_>>();
+        for chunk_size in 1..input.len() {
+            let mut parser = AnsiParser::new();
+            let mut markers = Vec::new();
+            let mut text = String::new();
+            let mut rest = input;
+            while !rest.is_empty() {
+                let mut end = chunk_size.min(rest.len());
+                while !rest.is_char_boundary(end) {
+                    end += 1;
+                }
+                let (chunk, tail) = rest.split_at(end);
+                rest = tail;
+                for marker in parser.feed(chunk) {
+                    match marker {
+                        Marker::Text(t) => text.push_str(t),
+                        Marker::Sequence(escape) => markers.push(escape),
+                    }
+                }
+            }
+            assert_eq!(parser.buffered(), "");
+            assert_eq!(text, "abcd");
+            assert_eq!(
+                markers,
+                expected
+                    .iter()
+                    .filter_map(|marker| match marker {
+                        Marker::Sequence(escape) => Some(escape.clone()),
+                        Marker::Text(_) => None,
+                    })
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn buffered() {
+        let mut parser = AnsiParser::new();
+        assert_eq!(parser.feed("abc\u{001b}]8;;https://").count(), 1);
+        assert_eq!(parser.buffered(), "\u{001b}]8;;https://");
+        assert_eq!(parser.feed("example.com\u{0007}").count(), 1);
+        assert_eq!(parser.buffered(), "");
+    }
+
+    #[test]
+    fn split_bytes() {
+        let input =
+            "a\u{001b}[31mcaf\u{e9}\u{001b}]0;\u{65e5}\u{672c}\u{0007}\u{1f600}d".as_bytes();
+        let expected = sequences::get_markers(str::from_utf8(input).unwrap())
+            .map(OwnedMarker::from)
+            .collect::<Vec<_>>();
+        for chunk_size in 1..input.len() {
+            let mut parser = AnsiParser::new();
+            let mut markers = Vec::new();
+            for chunk in input.chunks(chunk_size) {
+                for marker in parser.feed_bytes(chunk, InvalidBytes::Hex) {
+                    match (markers.last_mut(), marker) {
+                        (Some(OwnedMarker::Text(text)), Marker::Text(t)) => text.push_str(t),
+                        (_, marker) => markers.push(OwnedMarker::from(marker)),
+                    }
+                }
+            }
+            assert_eq!(markers, expected, "{}", chunk_size);
+        }
+
+        let mut parser = AnsiParser::new();
+        assert_eq!(parser.feed_bytes(b"\xe6\x97", InvalidBytes::Hex).count(), 0);
+        assert_eq!(
+            parser
+                .feed_bytes(b"x", InvalidBytes::Hex)
+                .collect::<Vec<_>>(),
+            vec![Marker::Text("\\xe6\\x97x")]
+        );
+    }
+
+    #[test]
+    fn long_sequences() {
+        let mut parser = AnsiParser::new();
+        assert_eq!(parser.feed("\u{001b}]0;").count(), 0);
+        for _ in 0..100_000 {
+            assert_eq!(parser.feed("0123456789").count(), 0);
+        }
+        assert_eq!(parser.buffered().len(), 1_000_004);
+        assert_eq!(
+            parser.feed("\u{001b}\\").collect::<Vec<_>>(),
+            vec![Marker::Sequence(Escape::Osc(
+                crate::Osc::SetIconNameAndTitle("0123456789".repeat(100_000))
+            ))]
+        );
+
+        // sequences which are never terminated are given up eventually
+        let chunk = "x".repeat(1024);
+        assert_eq!(parser.feed("\u{001b}_").count(), 0);
+        let mut given_up = 0;
+        while given_up == 0 {
+            given_up = parser
+                .feed(&chunk)
+                .filter(|marker| matches!(marker, Marker::Sequence(Escape::Unknown(_))))
+                .count();
+            assert!(parser.buffered().len() <= AnsiParser::MAX_BUFFERED);
+        }
+        assert_eq!(given_up, 1);
+        assert_eq!(parser.buffered(), "");
+        assert_eq!(
+            parser.feed("abc").collect::<Vec<_>>(),
+            vec![Marker::Text("abc")]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    bytes,
+    sequences::{self, Marker, ParseOptions},
+    stream::AnsiParser,
+};
+use std::{borrow::Cow, io, mem};
+
+/// Remove all escape sequences from the string.
+///
+/// This removes every kind of escape sequence, including unknown and invalid ones.
+/// Only allocates if the string contains any escape sequences.
+///
+/// ```
+/// # use yew_ansi::*;
+/// assert_eq!(
+///     yew_ansi::strip("\u{001b}[1mbold\u{001b}[0m \u{001b}]8;;https://example.com\u{0007}link\u{001b}]8;;\u{0007}"),
+///     "bold link"
+/// );
+/// ```
+pub fn strip(s: &str) -> Cow<'_, str> {
+    strip_with_options(s, ParseOptions::default())
+}
+
+/// Same as [`strip`] but with the given [`ParseOptions`].
+pub fn strip_with_options(s: &str, options: ParseOptions) -> Cow<'_, str> {
+    if !s.contains(|c| options.is_introducer(c)) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut text = String::with_capacity(s.len());
+    for marker in sequences::get_markers_with_options(s, options) {
+        if let Marker::Text(t) = marker {
+            text.push_str(t);
+        }
+    }
+    Cow::Owned(text)
+}
+
+/// Writer which removes all escape sequences before writing to the inner writer.
+///
+/// Escape sequences and UTF-8 characters which are split across multiple writes are handled correctly.
+/// Bytes which aren't valid UTF-8 are written unchanged unless they're part of an escape sequence.
+/// Like [`AnsiParser`], an escape sequence longer than [`AnsiParser::MAX_BUFFERED`] is given up on,
+/// so the rest of it is written as text.
+///
+/// ```
+/// # use yew_ansi::*;
+/// use std::io::Write;
+///
+/// let mut writer = StripWriter::new(Vec::new());
+/// writer.write_all(b"\x1b[3").unwrap();
+/// writer.write_all(b"1mred\x1b[0m\n").unwrap();
+/// assert_eq!(writer.finish().unwrap(), b"red\n");
+/// ```
+#[derive(Debug)]
+pub struct StripWriter<W: io::Write> {
+    inner: W,
+    parser: AnsiParser,
+    /// Incomplete UTF-8 character at the end of the last write.
+    pending: Vec<u8>,
+}
+impl<W: io::Write> StripWriter<W> {
+    /// Create a writer which strips the escape sequences before writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self::with_options(inner, ParseOptions::default())
+    }
+
+    /// Same as [`StripWriter::new`] but with the given [`ParseOptions`].
+    pub fn with_options(inner: W, options: ParseOptions) -> Self {
+        Self {
+            inner,
+            parser: AnsiParser::with_options(options),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Get a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner writer.
+    ///
+    /// Writing to it directly ends up in the middle of the output that is still buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Get the inner writer, discarding everything that is still buffered.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Finish writing and get the inner writer.
+    ///
+    /// An incomplete escape sequence at the end is discarded,
+    /// an incomplete UTF-8 character is written unchanged.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&self.pending)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_text(&mut self, s: &str) -> io::Result<()> {
+        for marker in self.parser.feed(s) {
+            if let Marker::Text(text) = marker {
+                self.inner.write_all(text.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+impl<W: io::Write> io::Write for StripWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut data = mem::take(&mut self.pending);
+        data.extend_from_slice(buf);
+
+        let mut chunks = bytes::utf8_chunks(&data).peekable();
+        while let Some((valid, invalid)) = chunks.next() {
+            self.write_text(valid)?;
+            if invalid.is_empty() {
+                continue;
+            }
+
+            // the last character might be completed by the next write
+            if chunks.peek().is_none() && bytes::is_incomplete_char(invalid) {
+                self.pending.extend_from_slice(invalid);
+            } else if self.parser.buffered().is_empty() {
+                self.inner.write_all(invalid)?;
+            } else {
+                self.write_text(&char::REPLACEMENT_CHARACTER.to_string())?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn stripping() {
+        assert!(matches!(strip("plain"), Cow::Borrowed("plain")));
+        assert_eq!(
+            strip("a\u{001b}[?25hb\u{001b}Pq#0~\u{001b}\\c\u{001b}(Bd\u{001b}_apc\u{001b}\\e"),
+            "abcde"
+        );
+        // unterminated and invalid sequences are removed as well
+        assert_eq!(strip("a\u{001b}]0;title\u{001b}[1mb\u{001b}["), "ab");
+        // tmux passthrough
+        assert_eq!(
+            strip("a\u{001b}Ptmux;\u{001b}\u{001b}[31m red\u{001b}\\b"),
+            "ab"
+        );
+
+        let c1 = ParseOptions { c1_controls: true };
+        assert_eq!(strip_with_options("\u{009b}1mbold", c1), "bold");
+        assert_eq!(strip("\u{009b}1mbold"), "\u{009b}1mbold");
+    }
+
+    #[test]
+    fn writer() {
+        let input = "caf\u{e9} \u{001b}[31mred\u{001b}]8;;https://example.com\u{001b}\\ \u{1f600}\u{001b}Ptmux;\u{001b}\u{001b}[1m\u{001b}\\\u{001b}[0m".as_bytes();
+        for chunk_size in 1..input.len() {
+            let mut writer = StripWriter::new(Vec::new());
+            for chunk in input.chunks(chunk_size) {
+                writer.write_all(chunk).unwrap();
+            }
+            let output = writer.finish().unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "caf\u{e9} red \u{1f600}"
+            );
+        }
+    }
+
+    #[test]
+    fn writer_buffer_is_limited() {
+        let mut writer = StripWriter::new(io::sink());
+        writer.write_all(b"a\x1b]0;").unwrap();
+        let chunk = [b'x'; 4096];
+        for _ in 0..=AnsiParser::MAX_BUFFERED / chunk.len() {
+            writer.write_all(&chunk).unwrap();
+            assert!(writer.parser.buffered().len() <= AnsiParser::MAX_BUFFERED);
+        }
+        assert_eq!(writer.parser.buffered(), "");
+    }
+
+    #[test]
+    fn writer_invalid_bytes() {
+        let mut writer = StripWriter::new(Vec::new());
+        writer.write_all(b"a\xff\x1b[1mb\xe2\x82").unwrap();
+        assert_eq!(writer.get_ref(), b"a\xffb");
+        assert_eq!(writer.finish().unwrap(), b"a\xffb\xe2\x82");
+    }
+}
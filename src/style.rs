@@ -1,4 +1,4 @@
-use crate::graphic_rendition::ColorEffect;
+use crate::graphic_rendition::{ColorEffect, UnderlineStyle};
 use std::borrow::Borrow;
 
 /// Combination of classes and inline styles.
@@ -35,6 +35,8 @@ impl ClassStyle {
 }
 
 /// Builder for [`ClassStyle`].
+///
+/// The attributes which have a default implementation are ignored unless the builder implements them.
 pub trait StyleBuilder: Default {
     /// Finish building and create a `ClassStyle`.
     fn finish(self) -> ClassStyle;
@@ -46,6 +48,34 @@ pub trait StyleBuilder: Default {
     /// Apply underline.
     fn underline(&mut self);
 
+    /// Apply an underline with the given style, which is never [`UnderlineStyle::None`].
+    ///
+    /// Defaults to [`StyleBuilder::underline`].
+    fn underline_style(&mut self, style: UnderlineStyle) {
+        let _ = style;
+        self.underline();
+    }
+    /// Set the underline colour.
+    /// This is only called if there is an underline.
+    fn underline_color(&mut self, color: &ColorEffect) {
+        let _ = color;
+    }
+    /// Apply faint (decreased intensity).
+    fn faint(&mut self) {}
+    /// Apply blinking.
+    fn blink(&mut self) {}
+    /// Apply inverse.
+    ///
+    /// This is called before the colours are set, which are already swapped.
+    /// It's only relevant for how the default colours are displayed.
+    fn inverse(&mut self) {}
+    /// Apply hidden.
+    fn hidden(&mut self) {}
+    /// Apply strikethrough.
+    fn strikethrough(&mut self) {}
+    /// Apply overline.
+    fn overline(&mut self) {}
+
     /// Set the foreground colour.
     fn fg_color(&mut self, color: &ColorEffect);
     /// Set the background colour.
@@ -53,40 +83,195 @@ pub trait StyleBuilder: Default {
 }
 
 /// Style builder using only inline style attributes.
+///
+/// Inverse text with default colours uses the `Canvas` and `CanvasText` system colours.
+/// CSS applies the decoration style and colour to every line of `text-decoration`,
+/// so the underline style and colour are only used if there's no other decoration.
 #[derive(Clone, Debug, Default)]
-pub struct InlineStyle(ClassStyle);
+pub struct InlineStyle {
+    class_style: ClassStyle,
+    /// Values for the `text-decoration-line` property.
+    decoration_lines: Vec<&'static str>,
+    underline_style: UnderlineStyle,
+    underline_color: Option<u32>,
+    inverse: bool,
+    hidden: bool,
+    fg: Option<u32>,
+    bg: Option<u32>,
+}
 impl InlineStyle {
     const CSS_BOLD: &'static str = "font-weight:bold;";
+    const CSS_FAINT: &'static str = "opacity:0.5;";
     const CSS_ITALIC: &'static str = "font-style:italic;";
-    const CSS_UNDERLINE: &'static str = "text-decoration:underline;";
+    const CSS_HIDDEN: &'static str = "color:transparent;";
+
+    fn push_decoration_line(&mut self, line: &'static str) {
+        if !self.decoration_lines.contains(&line) {
+            self.decoration_lines.push(line);
+        }
+    }
 }
 impl StyleBuilder for InlineStyle {
     fn finish(self) -> ClassStyle {
-        self.0
+        let Self {
+            mut class_style,
+            decoration_lines,
+            underline_style,
+            underline_color,
+            inverse,
+            hidden,
+            fg,
+            bg,
+        } = self;
+
+        if !decoration_lines.is_empty() {
+            class_style.push_style(format!("text-decoration:{};", decoration_lines.join(" ")));
+        }
+        if decoration_lines == ["underline"] {
+            let decoration_style = match underline_style {
+                UnderlineStyle::None | UnderlineStyle::Single => None,
+                UnderlineStyle::Double => Some("double"),
+                UnderlineStyle::Curly => Some("wavy"),
+                UnderlineStyle::Dotted => Some("dotted"),
+                UnderlineStyle::Dashed => Some("dashed"),
+            };
+            if let Some(decoration_style) = decoration_style {
+                class_style.push_style(format!("text-decoration-style:{};", decoration_style));
+            }
+            if let Some(code) = underline_color {
+                class_style.push_style(format!("text-decoration-color:#{:06x};", code));
+            }
+        }
+
+        if hidden {
+            class_style.push_style(Self::CSS_HIDDEN);
+        } else if let Some(code) = fg {
+            class_style.push_style(format!("color:#{:06x};", code));
+        } else if inverse {
+            class_style.push_style("color:Canvas;");
+        }
+
+        if let Some(code) = bg {
+            class_style.push_style(format!("background-color:#{:06x};", code));
+        } else if inverse {
+            class_style.push_style("background-color:CanvasText;");
+        }
+
+        class_style
     }
 
     fn bold(&mut self) {
-        self.0.push_style(Self::CSS_BOLD);
+        self.class_style.push_style(Self::CSS_BOLD);
     }
 
     fn italic(&mut self) {
-        self.0.push_style(Self::CSS_ITALIC);
+        self.class_style.push_style(Self::CSS_ITALIC);
     }
 
     fn underline(&mut self) {
-        self.0.push_style(Self::CSS_UNDERLINE);
+        self.push_decoration_line("underline");
+    }
+
+    fn faint(&mut self) {
+        self.class_style.push_style(Self::CSS_FAINT);
+    }
+
+    fn underline_style(&mut self, style: UnderlineStyle) {
+        self.push_decoration_line("underline");
+        self.underline_style = style;
+    }
+
+    fn underline_color(&mut self, color: &ColorEffect) {
+        self.underline_color = color.rgb();
+    }
+
+    fn blink(&mut self) {
+        // browsers are allowed to (and do) ignore this, but it doesn't hurt.
+        self.push_decoration_line("blink");
+    }
+
+    fn inverse(&mut self) {
+        self.inverse = true;
+    }
+
+    fn hidden(&mut self) {
+        self.hidden = true;
+    }
+
+    fn strikethrough(&mut self) {
+        self.push_decoration_line("line-through");
+    }
+
+    fn overline(&mut self) {
+        self.push_decoration_line("overline");
     }
 
     fn fg_color(&mut self, color: &ColorEffect) {
-        if let Some(code) = color.rgb() {
-            self.0.push_style(format!("color:#{:06x};", code));
-        }
+        self.fg = color.rgb();
     }
 
     fn bg_color(&mut self, color: &ColorEffect) {
-        if let Some(code) = color.rgb() {
-            self.0
-                .push_style(format!("background-color:#{:06x};", code));
-        }
+        self.bg = color.rgb();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphic_rendition::{ColorName, Sgr, SgrEffect};
+
+    fn style(sgrs: &[Sgr]) -> Option<String> {
+        let mut effect = SgrEffect::default();
+        effect.apply_sgrs(sgrs);
+        effect.to_class_style::<InlineStyle>().style
+    }
+
+    #[test]
+    fn inline_style() {
+        assert_eq!(style(&[]), None);
+        assert_eq!(
+            style(&[Sgr::Bold, Sgr::ColorFgName(ColorName::Red)]).as_deref(),
+            Some("font-weight:bold;color:#ff0000;")
+        );
+        assert_eq!(
+            style(&[Sgr::StyledUnderline(UnderlineStyle::Double)]).as_deref(),
+            Some("text-decoration:underline;text-decoration-style:double;")
+        );
+        // the style would apply to the other lines as well
+        assert_eq!(
+            style(&[
+                Sgr::StyledUnderline(UnderlineStyle::Double),
+                Sgr::ColorUnderlineName(ColorName::Red),
+                Sgr::Strikethrough,
+                Sgr::Overline
+            ])
+            .as_deref(),
+            Some("text-decoration:underline line-through overline;")
+        );
+        assert_eq!(
+            style(&[
+                Sgr::StyledUnderline(UnderlineStyle::Curly),
+                Sgr::ColorUnderlineName(ColorName::Red)
+            ])
+            .as_deref(),
+            Some("text-decoration:underline;text-decoration-style:wavy;text-decoration-color:#ff0000;")
+        );
+        assert_eq!(style(&[Sgr::ColorUnderlineName(ColorName::Red)]), None);
+        assert_eq!(
+            style(&[Sgr::Hidden, Sgr::ColorFgName(ColorName::Red)]).as_deref(),
+            Some("color:transparent;")
+        );
+    }
+
+    #[test]
+    fn inverse() {
+        assert_eq!(
+            style(&[Sgr::Inverse]).as_deref(),
+            Some("color:Canvas;background-color:CanvasText;")
+        );
+        assert_eq!(
+            style(&[Sgr::Inverse, Sgr::ColorFgName(ColorName::Red)]).as_deref(),
+            Some("color:Canvas;background-color:#ff0000;")
+        );
     }
 }
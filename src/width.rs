@@ -0,0 +1,178 @@
+use crate::{
+    graphic_rendition::SgrEffect,
+    osc::Osc,
+    sequences::{Csi, Escape, Marker},
+};
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthStr;
+
+/// Get the number of columns the text takes up in a terminal.
+///
+/// Escape sequences don't take up any space and wide characters (like CJK characters and most emoji)
+/// take up two columns.
+/// This is meant for a single line, line breaks aren't taken into account.
+///
+/// ```
+/// # use yew_ansi::*;
+/// assert_eq!(yew_ansi::visible_width("\u{001b}[31m日本\u{001b}[0m!"), 5);
+/// ```
+pub fn visible_width(s: &str) -> usize {
+    crate::get_sgr_segments(s)
+        .map(|(_, text)| text.width())
+        .sum()
+}
+
+/// Pad the text with spaces until it has the given [visible width](visible_width).
+///
+/// The padding uses the effect at the end of the text.
+/// Text which is already wide enough is returned unchanged.
+///
+/// ```
+/// # use yew_ansi::*;
+/// assert_eq!(yew_ansi::pad_to("\u{001b}[1mab", 4), "\u{001b}[1mab  ");
+/// ```
+pub fn pad_to(s: &str, width: usize) -> Cow<'_, str> {
+    let padding = width.saturating_sub(visible_width(s));
+    if padding == 0 {
+        return Cow::Borrowed(s);
+    }
+
+    let mut padded = String::with_capacity(s.len() + padding);
+    padded.push_str(s);
+    padded.extend(std::iter::repeat(' ').take(padding));
+    Cow::Owned(padded)
+}
+
+/// Truncate the text to the given [visible width](visible_width).
+///
+/// If the text is too wide, it's cut off and the `ellipsis` is added in its place.
+/// The ellipsis counts toward the width, if it doesn't fit on its own it's left out.
+/// Escape sequences before the cut are kept and the effect and hyperlink active at the cut
+/// are reset afterwards so that they don't apply to anything following the truncated text.
+/// Text which isn't too wide is returned unchanged.
+///
+/// ```
+/// # use yew_ansi::*;
+/// assert_eq!(
+///     yew_ansi::truncate_to("\u{001b}[31mred text\u{001b}[0m", 6, "…"),
+///     "\u{001b}[31mred t…\u{001b}[0m"
+/// );
+/// ```
+pub fn truncate_to<'a>(s: &'a str, width: usize, ellipsis: &str) -> Cow<'a, str> {
+    if visible_width(s) <= width {
+        return Cow::Borrowed(s);
+    }
+
+    let ellipsis = if ellipsis.width() <= width {
+        ellipsis
+    } else {
+        ""
+    };
+    let available = width - ellipsis.width();
+
+    let mut used = 0;
+    let mut cut = (0, SgrEffect::default());
+    for (effect, text, span) in crate::get_sgr_segments_with_spans(s) {
+        let text_width = text.width();
+        if used + text_width <= available {
+            used += text_width;
+            cut = (span.end, effect);
+            continue;
+        }
+
+        // sequences like emoji joined by ZWJ are narrower than their characters,
+        // so prefixes are measured just like `visible_width` measures the whole text.
+        let end = text
+            .char_indices()
+            .map(|(index, _)| index)
+            .skip(1)
+            .chain(std::iter::once(text.len()))
+            .take_while(|&end| used + text[..end].width() <= available)
+            .last()
+            .unwrap_or(0);
+        cut = (span.start + end, effect);
+        break;
+    }
+
+    let (end, effect) = cut;
+    let mut truncated = String::with_capacity(end + ellipsis.len() + 4);
+    truncated.push_str(&s[..end]);
+    truncated.push_str(ellipsis);
+    let reset = effect.transition(&SgrEffect::default());
+    if !reset.is_empty() {
+        truncated.push_str(&Csi::Sgr(reset).to_string());
+    }
+    let link_open = crate::get_markers(&s[..end]).fold(false, |open, marker| match marker {
+        Marker::Sequence(Escape::Osc(Osc::Hyperlink(link))) => link.is_some(),
+        _ => open,
+    });
+    if link_open {
+        truncated.push_str(&Escape::Osc(Osc::Hyperlink(None)).to_string());
+    }
+    Cow::Owned(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width() {
+        assert_eq!(visible_width(""), 0);
+        assert_eq!(visible_width("abc"), 3);
+        assert_eq!(visible_width("\u{001b}]0;title\u{0007}a\u{001b}[1m😀"), 3);
+        assert_eq!(visible_width("e\u{0301}"), 1);
+    }
+
+    #[test]
+    fn padding() {
+        assert!(matches!(pad_to("abc", 2), Cow::Borrowed("abc")));
+        assert_eq!(pad_to("日", 3), "日 ");
+    }
+
+    #[test]
+    fn truncating() {
+        assert!(matches!(
+            truncate_to("\u{001b}[1mabc", 3, "…"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(truncate_to("abcdef", 4, "..."), "a...");
+        assert_eq!(truncate_to("abcdef", 2, "..."), "ab");
+        // wide characters aren't split
+        assert_eq!(truncate_to("日本語", 4, "…"), "日…");
+        // the width matches the visible width
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let s = format!("a{}bc", family);
+        assert_eq!(
+            truncate_to(&s, visible_width(family) + 1, ""),
+            format!("a{}", family)
+        );
+        assert_eq!(truncate_to(&s, 2, ""), "a");
+        // sequences after the cut are dropped and the effect is reset
+        assert_eq!(
+            truncate_to("a\u{001b}[1mbc\u{001b}[31md", 2, ""),
+            "a\u{001b}[1mb\u{001b}[0m"
+        );
+        assert_eq!(
+            truncate_to("\u{001b}[1mab\u{001b}[22mcd", 3, ""),
+            "\u{001b}[1mab\u{001b}[22mc"
+        );
+        // an open hyperlink is closed
+        assert_eq!(
+            truncate_to(
+                "\u{001b}]8;;https://example.com\u{001b}\\link\u{001b}]8;;\u{001b}\\",
+                2,
+                ""
+            ),
+            "\u{001b}]8;;https://example.com\u{001b}\\li\u{001b}]8;;\u{001b}\\"
+        );
+        assert_eq!(
+            truncate_to(
+                "\u{001b}]8;;https://example.com\u{001b}\\a\u{001b}]8;;\u{001b}\\bc",
+                2,
+                ""
+            ),
+            "\u{001b}]8;;https://example.com\u{001b}\\a\u{001b}]8;;\u{001b}\\b"
+        );
+    }
+}
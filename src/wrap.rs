@@ -0,0 +1,323 @@
+use crate::lines::{AnsiLine, AnsiLineIter};
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Where lines may be broken when they're wrapped.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum WrapMode {
+    /// Break between words if possible.
+    ///
+    /// Whitespace at the break is removed.
+    /// Words which don't fit on a line of their own are broken anywhere.
+    #[default]
+    Word,
+    /// Break at the column limit like a terminal does, even in the middle of a word.
+    Hard,
+}
+
+/// Options for wrapping lines at a fixed width.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WrapOptions<'a> {
+    /// Maximum [visible width](crate::visible_width) of a line.
+    ///
+    /// Characters which are wider than this on their own still get a line.
+    pub width: usize,
+    /// Where lines may be broken.
+    pub mode: WrapMode,
+    /// Text which is put at the start of every continuation line.
+    ///
+    /// It's shown without any effect and counts toward the width.
+    pub continuation_marker: &'a str,
+}
+impl<'a> WrapOptions<'a> {
+    /// Wrap between words at the given width without marking continuation lines.
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            mode: WrapMode::default(),
+            continuation_marker: "",
+        }
+    }
+}
+
+/// Line produced by [wrapping](get_wrapped_lines) a longer line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WrappedLine<'a> {
+    /// Part of the original line, starting with the continuation marker if it continues the previous line.
+    pub line: AnsiLine<'a>,
+    /// Whether the line continues the previous one.
+    pub continued: bool,
+}
+
+#[derive(Debug)]
+struct WrapChar {
+    piece: usize,
+    span: Range<usize>,
+    width: usize,
+    whitespace: bool,
+}
+
+/// Find where the first row of the characters ends and where the next row starts.
+fn find_break(chars: &[WrapChar], available: usize, mode: WrapMode) -> (usize, usize) {
+    let trim = |mut end: usize| {
+        while end > 0 && chars[end - 1].whitespace {
+            end -= 1;
+        }
+        end
+    };
+
+    let mut used = 0;
+    let mut seen_word = false;
+    let mut word_start = None;
+    for (index, c) in chars.iter().enumerate() {
+        if !c.whitespace {
+            if seen_word && chars[index - 1].whitespace {
+                word_start = Some(index);
+            }
+            seen_word = true;
+        }
+
+        if used + c.width > available && index > 0 {
+            return match mode {
+                WrapMode::Hard => (index, index),
+                WrapMode::Word if c.whitespace => {
+                    let spaces = chars[index..].iter().take_while(|c| c.whitespace).count();
+                    (trim(index), index + spaces)
+                }
+                WrapMode::Word => match word_start {
+                    Some(start) => (trim(start), start),
+                    None => (index, index),
+                },
+            };
+        }
+
+        used += c.width;
+    }
+    (chars.len(), chars.len())
+}
+
+/// Wrap a single line made up of multiple pieces of text.
+///
+/// Returns the rows the line is split into.
+/// Each row consists of the index of the piece and the byte range of the piece that is part of the row.
+/// A row never contains a line break, the caller is responsible for splitting the text into lines first.
+pub(crate) fn wrap_line(pieces: &[&str], options: &WrapOptions) -> Vec<Vec<(usize, Range<usize>)>> {
+    let chars = pieces
+        .iter()
+        .enumerate()
+        .flat_map(|(piece, text)| {
+            text.char_indices().map(move |(start, c)| WrapChar {
+                piece,
+                span: start..start + c.len_utf8(),
+                width: c.width().unwrap_or_default(),
+                whitespace: c.is_whitespace(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let continuation_width = options
+        .width
+        .saturating_sub(options.continuation_marker.width());
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    loop {
+        let available = if rows.is_empty() {
+            options.width
+        } else {
+            continuation_width
+        };
+        let (end, next) = find_break(&chars[start..], available, options.mode);
+
+        let mut row: Vec<(usize, Range<usize>)> = Vec::new();
+        for c in &chars[start..start + end] {
+            match row.last_mut() {
+                Some((piece, span)) if *piece == c.piece => span.end = c.span.end,
+                _ => row.push((c.piece, c.span.clone())),
+            }
+        }
+        rows.push(row);
+
+        start += next;
+        if start >= chars.len() {
+            return rows;
+        }
+    }
+}
+
+/// Iterator over the lines in a string slice, wrapped at a fixed width.
+///
+/// Each item is a [`WrappedLine`].
+///
+/// Returned by [`get_wrapped_lines`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+#[derive(Clone, Debug)]
+pub struct WrappedLineIter<'a> {
+    lines: AnsiLineIter<'a>,
+    options: WrapOptions<'a>,
+    /// Remaining rows of the current line.
+    rows: std::vec::IntoIter<WrappedLine<'a>>,
+}
+impl<'a> WrappedLineIter<'a> {
+    fn new(s: &'a str, options: WrapOptions<'a>) -> Self {
+        Self {
+            lines: crate::get_ansi_lines(s),
+            options,
+            rows: Vec::new().into_iter(),
+        }
+    }
+
+    fn wrap(&self, line: AnsiLine<'a>) -> Vec<WrappedLine<'a>> {
+        let pieces = line
+            .segments
+            .iter()
+            .map(|(_, text)| *text)
+            .collect::<Vec<_>>();
+        let rows = wrap_line(&pieces, &self.options);
+
+        let mut start_effect = line.start_effect;
+        let mut lines = Vec::with_capacity(rows.len());
+        for (index, row) in rows.into_iter().enumerate() {
+            let continued = index > 0;
+            let mut segments = Vec::with_capacity(row.len() + 1);
+            if continued {
+                if let Some((piece, _)) = row.first() {
+                    start_effect = line.segments[*piece].0.clone();
+                }
+                if !self.options.continuation_marker.is_empty() {
+                    segments.push((Default::default(), self.options.continuation_marker));
+                }
+            }
+            for (piece, span) in row {
+                let (effect, text) = &line.segments[piece];
+                segments.push((effect.clone(), &text[span]));
+            }
+
+            lines.push(WrappedLine {
+                line: AnsiLine {
+                    start_effect: start_effect.clone(),
+                    segments,
+                },
+                continued,
+            });
+        }
+        lines
+    }
+}
+impl<'a> Iterator for WrappedLineIter<'a> {
+    type Item = WrappedLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.rows.next() {
+                return Some(line);
+            }
+            let line = self.lines.next()?;
+            self.rows = self.wrap(line).into_iter();
+        }
+    }
+}
+
+/// Create an iterator over the lines in a string slice which wraps lines that are too wide.
+///
+/// Works like [`get_ansi_lines`](crate::get_ansi_lines), but lines wider than [`WrapOptions::width`]
+/// are split into multiple lines.
+/// The segments keep their effect across the break and lines which continue the previous one
+/// are [marked](WrappedLine::continued).
+///
+/// ```
+/// # use yew_ansi::*;
+/// let options = WrapOptions {
+///     continuation_marker: "> ",
+///     ..WrapOptions::new(8)
+/// };
+/// let lines = yew_ansi::get_wrapped_lines("\u{001b}[1mhello big world", options)
+///     .map(|wrapped| {
+///         wrapped
+///             .line
+///             .segments
+///             .iter()
+///             .map(|(_, text)| *text)
+///             .collect::<String>()
+///     })
+///     .collect::<Vec<_>>();
+/// assert_eq!(lines, vec!["hello", "> big", "> world"]);
+/// ```
+pub fn get_wrapped_lines<'a>(s: &'a str, options: WrapOptions<'a>) -> WrappedLineIter<'a> {
+    WrappedLineIter::new(s, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphic_rendition::SgrEffect;
+
+    fn wrap(s: &str, options: WrapOptions) -> Vec<String> {
+        get_wrapped_lines(s, options)
+            .map(|wrapped| {
+                wrapped
+                    .line
+                    .segments
+                    .iter()
+                    .map(|(_, text)| *text)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn word_wrapping() {
+        let options = WrapOptions::new(5);
+        assert_eq!(wrap("", options), Vec::<String>::new());
+        assert_eq!(wrap("ab cd ef", options), vec!["ab cd", "ef"]);
+        assert_eq!(wrap("ab   cd", options), vec!["ab", "cd"]);
+        assert_eq!(wrap("abcdefgh ij", options), vec!["abcde", "fgh", "ij"]);
+        assert_eq!(wrap("a\n\nb c d e", options), vec!["a", "", "b c d", "e"]);
+        // indentation isn't a place to break
+        assert_eq!(wrap("  abcd", options), vec!["  abc", "d"]);
+        // wide characters
+        assert_eq!(wrap("日本 語", options), vec!["日本", "語"]);
+        assert_eq!(wrap("日", WrapOptions::new(1)), vec!["日"]);
+    }
+
+    #[test]
+    fn hard_wrapping() {
+        let options = WrapOptions {
+            mode: WrapMode::Hard,
+            ..WrapOptions::new(3)
+        };
+        assert_eq!(wrap("ab cd ef", options), vec!["ab ", "cd ", "ef"]);
+        assert_eq!(wrap("a\u{0301}bcd", options), vec!["a\u{0301}bc", "d"]);
+    }
+
+    #[test]
+    fn continuation() {
+        let options = WrapOptions {
+            mode: WrapMode::Hard,
+            continuation_marker: "…",
+            ..WrapOptions::new(3)
+        };
+        assert_eq!(wrap("abcdef", options), vec!["abc", "…de", "…f"]);
+
+        let bold = SgrEffect {
+            bold: true,
+            ..Default::default()
+        };
+        let lines = get_wrapped_lines("a\u{001b}[1mbcd", options).collect::<Vec<_>>();
+        assert_eq!(
+            lines[1],
+            WrappedLine {
+                line: AnsiLine {
+                    start_effect: bold.clone(),
+                    segments: vec![(SgrEffect::default(), "…"), (bold.clone(), "d")],
+                },
+                continued: true,
+            }
+        );
+        assert_eq!(
+            lines[0].line.segments,
+            vec![(SgrEffect::default(), "a"), (bold, "bc")]
+        );
+        assert!(!lines[0].continued);
+    }
+}
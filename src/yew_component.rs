@@ -1,8 +1,61 @@
-use crate::style::{ClassStyle, InlineStyle, StyleBuilder};
-use std::{borrow::Borrow, marker::PhantomData, rc::Rc};
-use yew::{html, Classes, Component, Html, Properties, Context};
+use crate::{
+    graphic_rendition::SgrEffect,
+    osc::{EmbeddedFile, Hyperlink, ImageDimension, Notification, Osc},
+    screen::{Embed, Screen, Segment},
+    sequences::{self, Csi, Escape, Marker, ParseOptions},
+    style::{ClassStyle, InlineStyle, StyleBuilder},
+    wrap::{self, WrapOptions},
+};
+use std::{borrow::Borrow, fmt::Write, marker::PhantomData, rc::Rc};
+use yew::{html, Callback, Classes, Component, Context, Html, Properties};
 
 const CSS_ANSI_CONTAINER: &str = "font-family:monospace;";
+const CSS_FILLED_LINE: &str = "display:flex;";
+const CSS_LINE_FILL: &str = "flex-grow:1;";
+const CSS_UNKNOWN_SEQUENCE: &str = "color:#808080;font-style:italic;";
+
+/// URL schemes which hyperlinks are allowed to use by default.
+///
+/// See [`AnsiProps::link_schemes`].
+pub const DEFAULT_LINK_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Styled text with an optional hyperlink.
+#[derive(Debug)]
+struct Span {
+    class_style: ClassStyle,
+    content: String,
+    href: Option<String>,
+}
+
+/// Events that were already reported to the callbacks.
+#[derive(Debug, Default)]
+struct ReportedEvents {
+    title: Option<String>,
+    working_directory: Option<String>,
+    /// Byte offset up to which the text was scanned for events.
+    scanned: usize,
+}
+
+/// Rendered piece of the content.
+#[derive(Debug)]
+enum Fragment {
+    Text(Span),
+    /// Line whose last span fills the rest of the line.
+    FilledLine {
+        spans: Vec<Span>,
+        fill: Span,
+    },
+    /// Image with its data URI.
+    Image {
+        src: String,
+        style: String,
+    },
+    /// Link to download a file.
+    Download {
+        href: String,
+        name: Option<String>,
+    },
+}
 
 /// Props that can be passed to the [`AnsiRenderer`] component.
 #[derive(Clone, Debug, PartialEq, Properties)]
@@ -15,10 +68,62 @@ pub struct AnsiProps<S: Clone + PartialEq> {
     /// Whether to disable the inline style applied to the root component. (Optional)
     #[prop_or_default]
     pub no_default_style: bool,
+    /// Whether to render the final state of a virtual [`Screen`] instead of the raw text. (Optional)
+    ///
+    /// This is required to correctly display text which moves the cursor around.
+    /// Images and files aren't shown in this mode.
+    #[prop_or_default]
+    pub screen: bool,
+    /// Whether to treat the 8-bit C1 control codes as escape sequences. (Optional)
+    ///
+    /// See [`ParseOptions::c1_controls`].
+    #[prop_or_default]
+    pub c1_controls: bool,
+    /// Whether to show unrecognised escape sequences instead of dropping them. (Optional)
+    ///
+    /// This includes string sequences like DCS and APC, which are dropped otherwise.
+    /// Sixel images are shown as their raw sequence too.
+    /// The sequences are shown where they appear in the input with their control characters made visible.
+    /// Line editing and the [`screen`](Self::screen) are disabled in this mode.
+    #[prop_or_default]
+    pub debug: bool,
+    /// URL schemes which hyperlinks are allowed to use. (Optional)
+    ///
+    /// Hyperlinks with any other scheme are rendered as plain text.
+    /// `javascript` links are never rendered, even if the scheme is listed here.
+    /// Defaults to [`DEFAULT_LINK_SCHEMES`].
+    #[prop_or(DEFAULT_LINK_SCHEMES)]
+    pub link_schemes: &'static [&'static str],
+    /// Wrap lines which are wider than the given width instead of relying on CSS. (Optional)
+    ///
+    /// Line breaks are inserted into the text, so the result looks like it was printed by a terminal
+    /// with that many columns.
+    /// Lines which fill the rest of the line with their background aren't wrapped.
+    #[prop_or_default]
+    pub wrap: Option<WrapOptions<'static>>,
+    /// Called with the last window title set by the content. (Optional)
+    ///
+    /// The callback is only called again if the title changes.
+    #[prop_or_default]
+    pub on_title: Option<Callback<String>>,
+    /// Called with the last working directory reported by the content. (Optional)
+    ///
+    /// The callback is only called again if the working directory changes.
+    #[prop_or_default]
+    pub on_working_directory: Option<Callback<String>>,
+    /// Called for every desktop notification in the content. (Optional)
+    ///
+    /// If the new content starts with the previous content, only the notifications
+    /// in the appended part are reported.
+    #[prop_or_default]
+    pub on_notification: Option<Callback<Notification>>,
 }
 
 /// Component for rendering text containing ANSI escape codes.
 ///
+/// Sixel graphics and PNG, JPEG, and GIF images sent using the iTerm2 protocol are rendered as `<img>` elements.
+/// Other files are offered as download links.
+///
 /// This takes two type arguments, `Text` and `Builder`.
 /// `Text` is the type that is passed to [`AnsiProps::text`]. It can be any type that implements [`Borrow<str>`][Borrow].
 /// `Builder` specifies the [`StyleBuilder`]. You probably want to use [`InlineStyle`].
@@ -36,7 +141,8 @@ where
     Builder: StyleBuilder,
 {
     props: AnsiProps<Text>,
-    segments: Vec<(ClassStyle, String)>,
+    fragments: Vec<Fragment>,
+    reported: ReportedEvents,
     _builder: PhantomData<Builder>,
 }
 impl<Text, Builder> AnsiRenderer<Text, Builder>
@@ -44,24 +150,275 @@ where
     Text: Borrow<str> + Clone + PartialEq,
     Builder: StyleBuilder,
 {
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            c1_controls: self.props.c1_controls,
+        }
+    }
+
     fn update_segments(&mut self) {
-        let s = &self.props.text;
-        self.segments.clear();
+        let options = self.parse_options();
+        let AnsiProps {
+            text,
+            screen,
+            link_schemes,
+            debug,
+            wrap,
+            ..
+        } = &self.props;
+        let fragments = &mut self.fragments;
+        fragments.clear();
+
+        if *debug {
+            Self::push_debug_fragments(fragments, text.borrow(), options, link_schemes);
+        } else if *screen {
+            let mut screen = Screen::with_options(options);
+            screen.feed(text.borrow());
+            for segment in screen.segments() {
+                Self::push_segment(fragments, segment, link_schemes);
+            }
+        } else {
+            for segment in crate::get_edited_segments_with_options(text.borrow(), options).merged()
+            {
+                Self::push_segment(fragments, segment, link_schemes);
+            }
+        }
+
+        if let Some(options) = wrap {
+            *fragments = wrap_fragments(std::mem::take(fragments), options);
+        }
+    }
+
+    /// Push the text with the unrecognised sequences shown in place.
+    fn push_debug_fragments(
+        fragments: &mut Vec<Fragment>,
+        text: &str,
+        options: ParseOptions,
+        link_schemes: &[&str],
+    ) {
+        let mut effect = SgrEffect::default();
+        let mut link = None;
+        for (marker, span) in sequences::get_markers_with_options(text, options).with_spans() {
+            match marker {
+                Marker::Text(text) => {
+                    let segment = Segment {
+                        link: link.clone(),
+                        ..Segment::new(effect.clone(), text)
+                    };
+                    Self::push_segment(fragments, segment, link_schemes);
+                }
+                Marker::Sequence(Escape::Csi(Csi::Sgr(sgrs))) => effect.apply_sgrs(sgrs),
+                Marker::Sequence(Escape::Osc(Osc::Hyperlink(hyperlink))) => {
+                    link = hyperlink.map(Rc::new);
+                }
+                Marker::Sequence(
+                    Escape::Csi(Csi::Unknown(_))
+                    | Escape::Unknown(_)
+                    | Escape::Dcs(_)
+                    | Escape::Apc(_)
+                    | Escape::Pm(_)
+                    | Escape::Sos(_),
+                ) => {
+                    let mut class_style = ClassStyle::default();
+                    class_style.push_style(CSS_UNKNOWN_SEQUENCE);
+                    fragments.push(Fragment::Text(Span {
+                        class_style,
+                        content: visible_controls(&text[span]),
+                        href: None,
+                    }));
+                }
+                Marker::Sequence(_) => {}
+            }
+        }
+    }
+
+    /// Report the events in the content to the callbacks.
+    /// If `appended` is true, only the part of the content which wasn't scanned yet is scanned,
+    /// so events which were already reported aren't reported again.
+    fn report_events(&mut self, appended: bool) {
+        let options = self.parse_options();
+        let AnsiProps {
+            text,
+            on_title,
+            on_working_directory,
+            on_notification,
+            ..
+        } = &self.props;
+        if on_title.is_none() && on_working_directory.is_none() && on_notification.is_none() {
+            return;
+        }
+
+        let reported = &mut self.reported;
+        if !appended {
+            *reported = ReportedEvents::default();
+        }
+        let text = text.borrow();
+        let start = reported.scanned.min(text.len());
+
+        let mut title = None;
+        let mut working_directory = None;
+        let mut notifications = Vec::new();
+        reported.scanned = text.len();
+        for (marker, span) in
+            sequences::get_markers_with_options(&text[start..], options).with_spans()
+        {
+            match marker {
+                Marker::Sequence(Escape::Osc(
+                    Osc::SetTitle(value) | Osc::SetIconNameAndTitle(value),
+                )) => title = Some(value),
+                Marker::Sequence(Escape::Osc(Osc::WorkingDirectory(value))) => {
+                    working_directory = Some(value)
+                }
+                Marker::Sequence(Escape::Osc(Osc::Notification(notification))) => {
+                    notifications.push(notification)
+                }
+                // the sequence might be incomplete, scan it again once more text is appended
+                Marker::Sequence(Escape::Unknown(_)) if start + span.end == text.len() => {
+                    reported.scanned = start + span.start;
+                }
+                _ => {}
+            }
+        }
 
-        for (effect, content) in crate::get_sgr_segments(s.borrow()) {
-            self.segments
-                .push((effect.to_class_style::<Builder>(), content.to_owned()))
+        if let (Some(callback), Some(title)) = (on_title, title) {
+            if reported.title.as_ref() != Some(&title) {
+                callback.emit(title.clone());
+                reported.title = Some(title);
+            }
+        }
+        if let (Some(callback), Some(working_directory)) = (on_working_directory, working_directory)
+        {
+            if reported.working_directory.as_ref() != Some(&working_directory) {
+                callback.emit(working_directory.clone());
+                reported.working_directory = Some(working_directory);
+            }
+        }
+        if let Some(callback) = on_notification {
+            for notification in notifications {
+                callback.emit(notification);
+            }
         }
     }
 
-    fn render_segment((class_style, content): &(ClassStyle, String)) -> Html {
+    /// Get the URI of the hyperlink if its scheme is allowed.
+    fn allowed_href(link: &Hyperlink, link_schemes: &[&str]) -> Option<String> {
+        let scheme = link.scheme()?;
+        let allowed = !scheme.eq_ignore_ascii_case("javascript")
+            && link_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme));
+        if allowed {
+            Some(link.uri.clone())
+        } else {
+            None
+        }
+    }
+
+    fn embed_fragment(embed: &Embed) -> Fragment {
+        match embed {
+            Embed::Sixel(image) => Fragment::Image {
+                src: svg_data_uri(&image.to_svg()),
+                style: format!("width:{}px;height:{}px;", image.width(), image.height()),
+            },
+            Embed::File(file) => match file.image_type() {
+                Some(image_type) if file.inline => Fragment::Image {
+                    src: format!("data:{};base64,{}", image_type, file.data),
+                    style: file_image_style(file),
+                },
+                _ => Fragment::Download {
+                    href: format!("data:application/octet-stream;base64,{}", file.data),
+                    name: file.name.clone(),
+                },
+            },
+        }
+    }
+
+    fn push_segment(fragments: &mut Vec<Fragment>, segment: Segment, link_schemes: &[&str]) {
+        if let Some(embed) = &segment.image {
+            fragments.push(Self::embed_fragment(embed));
+            return;
+        }
+
+        let span = Span {
+            class_style: segment.effect.to_class_style::<Builder>(),
+            content: segment.text.into_owned(),
+            href: segment
+                .link
+                .and_then(|link| Self::allowed_href(&link, link_schemes)),
+        };
+        if !segment.fill {
+            fragments.push(Fragment::Text(span));
+            return;
+        }
+
+        // move everything after the last newline into the filled line
+        let mut spans = Vec::new();
+        while let Some(Fragment::Text(last)) = fragments.last_mut() {
+            if let Some(index) = last.content.rfind('\n') {
+                let tail = last.content.split_off(index + 1);
+                if !tail.is_empty() {
+                    spans.push(Span {
+                        class_style: last.class_style.clone(),
+                        content: tail,
+                        href: last.href.clone(),
+                    });
+                }
+                break;
+            }
+
+            if let Some(Fragment::Text(last)) = fragments.pop() {
+                spans.push(last);
+            }
+        }
+        spans.reverse();
+
+        fragments.push(Fragment::FilledLine { spans, fill: span });
+    }
+
+    fn render_span(span: &Span) -> Html {
+        Self::render_span_with_style(span, &span.class_style)
+    }
+
+    fn render_span_with_style(span: &Span, class_style: &ClassStyle) -> Html {
         // TODO update to use optional attributes when they land
         let class = class_style.class.clone();
         let style = class_style.style.clone().unwrap_or_default();
-        html! {
+        let html = html! {
             <span class={ class } style={ style }>
-                { content }
+                { &span.content }
             </span>
+        };
+
+        match &span.href {
+            Some(href) => html! {
+                <a href={ href.clone() }>{ html }</a>
+            },
+            None => html,
+        }
+    }
+
+    fn render_fragment(fragment: &Fragment) -> Html {
+        match fragment {
+            Fragment::Text(span) => Self::render_span(span),
+            Fragment::FilledLine { spans, fill } => {
+                let mut fill_style = fill.class_style.clone();
+                fill_style.push_style(CSS_LINE_FILL);
+                html! {
+                    <div style={ CSS_FILLED_LINE }>
+                        { for spans.iter().map(Self::render_span) }
+                        { Self::render_span_with_style(fill, &fill_style) }
+                    </div>
+                }
+            }
+            Fragment::Image { src, style } => html! {
+                <img src={ src.clone() } style={ style.clone() } />
+            },
+            Fragment::Download { href, name } => {
+                let label = name.as_deref().unwrap_or("download");
+                html! {
+                    <a href={ href.clone() } download={ name.clone().unwrap_or_default() }>{ label }</a>
+                }
+            }
         }
     }
 }
@@ -76,10 +433,12 @@ where
     fn create(ctx: &Context<Self>) -> Self {
         let mut instance = Self {
             props: ctx.props().clone(),
-            segments: Vec::new(),
-            _builder: PhantomData::default(),
+            fragments: Vec::new(),
+            reported: ReportedEvents::default(),
+            _builder: PhantomData,
         };
         instance.update_segments();
+        instance.report_events(false);
         instance
     }
 
@@ -88,7 +447,19 @@ where
     }
 
     fn changed(&mut self, ctx: &Context<Self>, _old: &Self::Properties) -> bool {
-        let update_segments = self.props.text != ctx.props().text;
+        let text_changed = self.props.text != ctx.props().text;
+        let options_changed = self.props.c1_controls != ctx.props().c1_controls;
+        let appended = ctx
+            .props()
+            .text
+            .borrow()
+            .starts_with(self.props.text.borrow());
+        let update_segments = text_changed
+            || options_changed
+            || self.props.screen != ctx.props().screen
+            || self.props.debug != ctx.props().debug
+            || self.props.link_schemes != ctx.props().link_schemes
+            || self.props.wrap != ctx.props().wrap;
 
         let should_render = if &self.props == ctx.props() {
             false
@@ -100,6 +471,9 @@ where
         if update_segments {
             self.update_segments();
         }
+        if text_changed || options_changed {
+            self.report_events(appended);
+        }
 
         should_render
     }
@@ -112,12 +486,130 @@ where
         };
         html! {
             <pre class={ ctx.props().class.clone() } style={ style }>
-                { for self.segments.iter().map(Self::render_segment) }
+                { for self.fragments.iter().map(Self::render_fragment) }
             </pre>
         }
     }
 }
 
+/// Replace the control characters in the text with visible ones.
+/// C0 controls are replaced with the corresponding control pictures, C1 controls are escaped.
+fn visible_controls(s: &str) -> String {
+    let mut visible = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{0000}'..='\u{001f}' => visible.extend(char::from_u32(0x2400 + c as u32)),
+            '\u{007f}' => visible.push('\u{2421}'),
+            c if c.is_control() => visible.extend(c.escape_unicode()),
+            c => visible.push(c),
+        }
+    }
+    visible
+}
+
+/// Insert line breaks into the text fragments so that no line is wider than allowed.
+///
+/// Other fragments are left untouched and end the current line.
+fn wrap_fragments(fragments: Vec<Fragment>, options: &WrapOptions) -> Vec<Fragment> {
+    fn plain_span(content: &str) -> Fragment {
+        Fragment::Text(Span {
+            class_style: ClassStyle::default(),
+            content: content.to_owned(),
+            href: None,
+        })
+    }
+
+    fn flush_line(wrapped: &mut Vec<Fragment>, line: &mut Vec<Span>, options: &WrapOptions) {
+        let pieces = line
+            .iter()
+            .map(|span| span.content.as_str())
+            .collect::<Vec<_>>();
+        for (index, row) in wrap::wrap_line(&pieces, options).into_iter().enumerate() {
+            if index > 0 {
+                wrapped.push(plain_span("\n"));
+                if !options.continuation_marker.is_empty() {
+                    wrapped.push(plain_span(options.continuation_marker));
+                }
+            }
+            for (piece, range) in row {
+                let span = &line[piece];
+                wrapped.push(Fragment::Text(Span {
+                    class_style: span.class_style.clone(),
+                    content: span.content[range].to_owned(),
+                    href: span.href.clone(),
+                }));
+            }
+        }
+        line.clear();
+    }
+
+    let mut wrapped = Vec::with_capacity(fragments.len());
+    let mut line = Vec::new();
+    for fragment in fragments {
+        let span = match fragment {
+            Fragment::Text(span) => span,
+            fragment => {
+                flush_line(&mut wrapped, &mut line, options);
+                wrapped.push(fragment);
+                continue;
+            }
+        };
+
+        let mut parts = span.content.split('\n').peekable();
+        while let Some(part) = parts.next() {
+            line.push(Span {
+                class_style: span.class_style.clone(),
+                content: part.to_owned(),
+                href: span.href.clone(),
+            });
+            if parts.peek().is_some() {
+                flush_line(&mut wrapped, &mut line, options);
+                wrapped.push(plain_span("\n"));
+            }
+        }
+    }
+    flush_line(&mut wrapped, &mut line, options);
+    wrapped
+}
+
+/// Get the style for an inline image sent using the iTerm2 protocol.
+/// Sizes in cells are relative to the character width and the line height.
+fn file_image_style(file: &EmbeddedFile) -> String {
+    let mut style = String::new();
+    for (property, dimension, cell_unit) in
+        [("width", file.width, "ch"), ("height", file.height, "lh")]
+    {
+        let _ = match dimension {
+            ImageDimension::Auto => Ok(()),
+            ImageDimension::Cells(n) => write!(style, "{}:{}{};", property, n, cell_unit),
+            ImageDimension::Pixels(n) => write!(style, "{}:{}px;", property, n),
+            ImageDimension::Percent(n) => write!(style, "{}:{}%;", property, n),
+        };
+    }
+    if file.preserve_aspect_ratio {
+        style.push_str("object-fit:contain;");
+    } else {
+        style.push_str("object-fit:fill;");
+    }
+    style
+}
+
+/// Create a data URI for the SVG image.
+fn svg_data_uri(svg: &str) -> String {
+    let mut uri = String::from("data:image/svg+xml,");
+    for c in svg.chars() {
+        match c {
+            '"' => uri.push_str("%22"),
+            '#' => uri.push_str("%23"),
+            '%' => uri.push_str("%25"),
+            '<' => uri.push_str("%3C"),
+            '>' => uri.push_str("%3E"),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
 /// ANSI component which takes a [`String`].
 ///
 /// See [`AnsiRenderer`] for more details.
@@ -130,3 +622,160 @@ pub type AnsiRc<Builder = InlineStyle> = AnsiRenderer<Rc<String>, Builder>;
 ///
 /// See [`AnsiRenderer`] for more details.
 pub type AnsiStatic<Builder = InlineStyle> = AnsiRenderer<&'static str, Builder>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    type Renderer = AnsiRenderer<String, InlineStyle>;
+
+    fn renderer(text: &str) -> Renderer {
+        Renderer {
+            props: AnsiProps {
+                class: Classes::new(),
+                text: text.to_owned(),
+                no_default_style: false,
+                screen: false,
+                c1_controls: false,
+                debug: false,
+                link_schemes: DEFAULT_LINK_SCHEMES,
+                wrap: None,
+                on_title: None,
+                on_working_directory: None,
+                on_notification: None,
+            },
+            fragments: Vec::new(),
+            reported: ReportedEvents::default(),
+            _builder: PhantomData,
+        }
+    }
+
+    /// Get the text of every fragment, filled lines are joined into one.
+    fn contents(fragments: &[Fragment]) -> Vec<String> {
+        fragments
+            .iter()
+            .map(|fragment| match fragment {
+                Fragment::Text(span) => span.content.clone(),
+                Fragment::FilledLine { spans, fill } => spans
+                    .iter()
+                    .chain(std::iter::once(fill))
+                    .map(|span| span.content.as_str())
+                    .collect(),
+                Fragment::Image { src, .. } => src.clone(),
+                Fragment::Download { href, .. } => href.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn allowed_href() {
+        let href = |uri: &str, link_schemes: &[&str]| {
+            let link = Hyperlink {
+                id: None,
+                uri: uri.to_owned(),
+            };
+            Renderer::allowed_href(&link, link_schemes)
+        };
+        assert_eq!(
+            href("https://example.com", DEFAULT_LINK_SCHEMES).as_deref(),
+            Some("https://example.com")
+        );
+        assert!(href("HTTP://example.com", DEFAULT_LINK_SCHEMES).is_some());
+        assert!(href("mailto:me@example.com", DEFAULT_LINK_SCHEMES).is_some());
+        assert_eq!(href("data:text/html,<b>hi</b>", DEFAULT_LINK_SCHEMES), None);
+        assert_eq!(href("/relative/path", DEFAULT_LINK_SCHEMES), None);
+        assert_eq!(href("relative:path", DEFAULT_LINK_SCHEMES), None);
+        // javascript is never allowed
+        assert_eq!(href("javascript:alert(1)", DEFAULT_LINK_SCHEMES), None);
+        assert_eq!(href("JavaScript:alert(1)", &["javascript"]), None);
+        assert!(href("data:text/plain,hi", &["DATA"]).is_some());
+    }
+
+    #[test]
+    fn filled_line() {
+        let red = SgrEffect {
+            bg: crate::ColorEffect::Name(crate::ColorName::Red),
+            ..Default::default()
+        };
+        let mut fragments = Vec::new();
+        for segment in [
+            Segment::new(SgrEffect::default(), "a\nb"),
+            Segment::new(red.clone(), "c"),
+            Segment {
+                fill: true,
+                ..Segment::new(red, "")
+            },
+        ] {
+            Renderer::push_segment(&mut fragments, segment, DEFAULT_LINK_SCHEMES);
+        }
+        assert_eq!(contents(&fragments), vec!["a\n", "bc"]);
+        assert!(matches!(
+            &fragments[1],
+            Fragment::FilledLine { spans, .. } if spans.len() == 2
+        ));
+    }
+
+    #[test]
+    fn incremental_events() {
+        let titles = Rc::new(RefCell::new(Vec::<String>::new()));
+        let mut renderer = renderer("\u{001b}]0;one\u{0007}text\u{001b}]0;tw");
+        renderer.props.on_title = Some(Callback::from({
+            let titles = titles.clone();
+            move |title| titles.borrow_mut().push(title)
+        }));
+        renderer.report_events(false);
+        assert_eq!(titles.take(), vec!["one"]);
+
+        // the incomplete sequence is scanned again
+        renderer.props.text.push_str("o\u{0007}");
+        renderer.report_events(true);
+        assert_eq!(titles.take(), vec!["two"]);
+
+        // the same title isn't reported twice
+        renderer.props.text.push_str("\u{001b}]0;two\u{0007}");
+        renderer.report_events(true);
+        assert_eq!(titles.take(), Vec::<String>::new());
+
+        // everything is scanned again if the text was replaced
+        renderer.props.text = "\u{001b}]0;two\u{0007}".to_owned();
+        renderer.report_events(false);
+        assert_eq!(titles.take(), vec!["two"]);
+    }
+
+    #[test]
+    fn wrapping() {
+        let span = |content: &str| {
+            Fragment::Text(Span {
+                class_style: ClassStyle::default(),
+                content: content.to_owned(),
+                href: None,
+            })
+        };
+        let options = WrapOptions {
+            continuation_marker: ">",
+            ..WrapOptions::new(4)
+        };
+        let fragments = vec![
+            span("ab"),
+            span("cdef\ngh"),
+            Fragment::Image {
+                src: "image".to_owned(),
+                style: String::new(),
+            },
+            span("ijklm"),
+        ];
+        assert_eq!(
+            contents(&wrap_fragments(fragments, &options)).concat(),
+            "abcd\n>ef\nghimageijkl\n>m"
+        );
+    }
+
+    #[test]
+    fn svg_uri() {
+        assert_eq!(
+            svg_data_uri(r##"<svg fill="#fff" x="1%"/>"##),
+            "data:image/svg+xml,%3Csvg fill=%22%23fff%22 x=%221%25%22/%3E"
+        );
+    }
+}